@@ -0,0 +1,243 @@
+//! Achievements earned in play, kept between sessions.
+//!
+//! Unlocked achievements are stored under `achievements` as a comma-separated
+//! list of keys, and the daily challenges won under `daily_wins`.
+use crate::storage;
+use crate::{
+    Cli, EnemyReturned, Explosion, GameSeed, GameState, GameTime, IsEnemy, IsGlod, RoundClock,
+    ShipLaunched, Team, Tutorial,
+};
+use bevy::prelude::*;
+use std::collections::BTreeSet;
+
+pub struct AchievementPlugin;
+
+impl Plugin for AchievementPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Achievements::from_storage())
+            .insert_resource(RoundStats::default())
+            .add_event::<AchievementUnlocked>()
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(reset_stats))
+            .add_system_set(
+                SystemSet::on_update(GameState::Game)
+                    .with_system(track_shots)
+                    .with_system(track_glod)
+                    .with_system(mark_blasted_enemies)
+                    .with_system(knockout.after("respawn_enemies")),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Victory).with_system(victory_achievements),
+            );
+    }
+}
+
+/// Fastest win that counts as a speed run.
+const SPEED_RUN_SECONDS: f32 = 30.0;
+/// Daily challenges to win for the regular achievement.
+const DAILY_WINS: usize = 10;
+/// How long after an explosion an enemy leaving the arena still counts as knocked out.
+const BLAST_SECONDS: f32 = 3.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Achievement {
+    Pacifist,
+    CleanSweep,
+    Knockout,
+    SpeedRun,
+    Regular,
+}
+
+pub const ACHIEVEMENTS: [Achievement; 5] = [
+    Achievement::Pacifist,
+    Achievement::CleanSweep,
+    Achievement::Knockout,
+    Achievement::SpeedRun,
+    Achievement::Regular,
+];
+
+impl Achievement {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Achievement::Pacifist => "Pacifist",
+            Achievement::CleanSweep => "Clean sweep",
+            Achievement::Knockout => "Knockout",
+            Achievement::SpeedRun => "Speed run",
+            Achievement::Regular => "Regular",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Achievement::Pacifist => "Win without firing a torpedo",
+            Achievement::CleanSweep => "Collect every glod on the map",
+            Achievement::Knockout => "Blast the enemy out of the arena",
+            Achievement::SpeedRun => "Win in under 30 seconds",
+            Achievement::Regular => "Win 10 daily challenges",
+        }
+    }
+
+    fn key(&self) -> &'static str {
+        match self {
+            Achievement::Pacifist => "pacifist",
+            Achievement::CleanSweep => "clean-sweep",
+            Achievement::Knockout => "knockout",
+            Achievement::SpeedRun => "speed-run",
+            Achievement::Regular => "regular",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Achievement> {
+        ACHIEVEMENTS.into_iter().find(|a| a.key() == key)
+    }
+}
+
+/// Sent the moment an achievement is first earned.
+pub struct AchievementUnlocked(pub Achievement);
+
+#[derive(Resource, Default)]
+pub struct Achievements {
+    unlocked: BTreeSet<Achievement>,
+    /// Daily seeds won, so winning the same day twice counts once.
+    daily_wins: BTreeSet<String>,
+}
+
+impl Achievements {
+    fn from_storage() -> Achievements {
+        let list = |key| -> Vec<String> {
+            storage::load(key)
+                .unwrap_or_default()
+                .split(',')
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        Achievements {
+            unlocked: list("achievements")
+                .iter()
+                .filter_map(|key| Achievement::from_key(key))
+                .collect(),
+            daily_wins: list("daily_wins").into_iter().collect(),
+        }
+    }
+
+    pub fn is_unlocked(&self, achievement: Achievement) -> bool {
+        self.unlocked.contains(&achievement)
+    }
+
+    fn unlock(&mut self, achievement: Achievement, events: &mut EventWriter<AchievementUnlocked>) {
+        if self.unlocked.insert(achievement) {
+            let keys: Vec<&str> = self.unlocked.iter().map(Achievement::key).collect();
+            storage::save("achievements", &keys.join(","));
+            events.send(AchievementUnlocked(achievement));
+        }
+    }
+
+    /// Records a daily challenge win, returning how many have been won.
+    fn win_daily(&mut self, seed: &GameSeed) -> usize {
+        if self.daily_wins.insert(seed.0.clone()) {
+            let seeds: Vec<&str> = self.daily_wins.iter().map(String::as_str).collect();
+            storage::save("daily_wins", &seeds.join(","));
+        }
+        self.daily_wins.len()
+    }
+}
+
+/// What happened in the current game, for achievements judged at the end.
+#[derive(Resource, Clone, Default)]
+pub struct RoundStats {
+    fired: bool,
+    seen_glod: bool,
+}
+
+/// An enemy recently caught in an explosion.
+#[derive(Component, Clone)]
+pub struct Blasted(Timer);
+
+/// Practice, replays and hand-made levels don't earn anything.
+pub fn counts(cli: &Cli, tutorial: &Tutorial) -> bool {
+    cli.replay.is_none() && cli.level.is_none() && !tutorial.active()
+}
+
+fn reset_stats(mut stats: ResMut<RoundStats>) {
+    *stats = RoundStats::default();
+}
+
+fn track_shots(mut stats: ResMut<RoundStats>, torpedoes: Query<(), Added<ShipLaunched>>) {
+    if !torpedoes.is_empty() {
+        stats.fired = true;
+    }
+}
+
+fn track_glod(
+    cli: Res<Cli>,
+    tutorial: Res<Tutorial>,
+    glods: Query<(), With<IsGlod>>,
+    mut stats: ResMut<RoundStats>,
+    mut achievements: ResMut<Achievements>,
+    mut events: EventWriter<AchievementUnlocked>,
+) {
+    if !glods.is_empty() {
+        stats.seen_glod = true;
+    } else if stats.seen_glod && counts(&cli, &tutorial) {
+        achievements.unlock(Achievement::CleanSweep, &mut events);
+    }
+}
+
+fn mark_blasted_enemies(
+    mut commands: Commands,
+    time: GameTime,
+    explosions: Query<(&Transform, &Explosion), (Added<Explosion>, With<Team>)>,
+    mut enemies: Query<(Entity, &Transform, Option<&mut Blasted>), With<IsEnemy>>,
+) {
+    for (enemy, trans, blasted) in enemies.iter_mut() {
+        let caught = explosions.iter().any(|(blast, explosion)| {
+            blast.translation.distance(trans.translation) <= explosion.radius
+        });
+        if caught {
+            let timer = Timer::from_seconds(BLAST_SECONDS, TimerMode::Once);
+            commands.entity(enemy).insert(Blasted(timer));
+        } else if let Some(mut blasted) = blasted {
+            if blasted.0.tick(time.delta()).finished() {
+                commands.entity(enemy).remove::<Blasted>();
+            }
+        }
+    }
+}
+
+fn knockout(
+    cli: Res<Cli>,
+    tutorial: Res<Tutorial>,
+    blasted: Query<(), With<Blasted>>,
+    mut returned: EventReader<EnemyReturned>,
+    mut achievements: ResMut<Achievements>,
+    mut events: EventWriter<AchievementUnlocked>,
+) {
+    for EnemyReturned(enemy) in returned.iter() {
+        if blasted.contains(*enemy) && counts(&cli, &tutorial) {
+            achievements.unlock(Achievement::Knockout, &mut events);
+        }
+    }
+}
+
+fn victory_achievements(
+    cli: Res<Cli>,
+    tutorial: Res<Tutorial>,
+    stats: Res<RoundStats>,
+    clock: Res<RoundClock>,
+    seed: Res<GameSeed>,
+    mut achievements: ResMut<Achievements>,
+    mut events: EventWriter<AchievementUnlocked>,
+) {
+    if !counts(&cli, &tutorial) {
+        return;
+    }
+    if !stats.fired {
+        achievements.unlock(Achievement::Pacifist, &mut events);
+    }
+    if clock.seconds() < SPEED_RUN_SECONDS {
+        achievements.unlock(Achievement::SpeedRun, &mut events);
+    }
+    if *seed == GameSeed::daily() && achievements.win_daily(&seed) >= DAILY_WINS {
+        achievements.unlock(Achievement::Regular, &mut events);
+    }
+}
@@ -0,0 +1,259 @@
+//! Neutral asteroids that drift, bounce off everything and get blasted about.
+//!
+//! They are placed by their own generator seeded from `GameSeed`, so the map
+//! layout stays the same with or without them. The enemy is a sensor and
+//! passes through rigid bodies, so asteroids hold it back by hand here, and
+//! one moving fast enough sends it back to the enemy base.
+use crate::{AsteroidRule, CollisionFilters, CollisionMemberships};
+use crate::{
+    Explodee, Explosion, FoeStartingPoint, FriendStartingPoint, GameSeed, GameState, IsEnemy,
+    PlayerMode, RivalStartingPoint, DRIFT_EDGE, MAX_DRAWS, START_RADIUS,
+};
+use bevy::prelude::*;
+use bevy_rapier2d::geometry::Group;
+use bevy_rapier2d::prelude::*;
+use rand::prelude::*;
+use rand_pcg::Pcg64;
+use rand_seeder::Seeder;
+use std::f32::consts::TAU;
+
+pub struct AsteroidPlugin;
+
+impl Plugin for AsteroidPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Game).with_system(spawn_asteroids))
+            .add_system_set(
+                SystemSet::on_update(GameState::Game)
+                    .with_system(bounce_asteroids)
+                    .with_system(split_asteroids)
+                    .with_system(block_enemies),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Victory).with_system(despawn_asteroids))
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver).with_system(despawn_asteroids),
+            );
+    }
+}
+
+/// Asteroids are kept at least this far from the edge of every base.
+const BASE_CLEARANCE: f32 = 30.0;
+const ASTEROID_SPEED: std::ops::Range<f32> = 5.0..25.0;
+/// Speed each half of a split asteroid flies apart at.
+const SPLIT_SPEED: f32 = 30.0;
+/// Roughly how far an enemy reaches from its centre.
+const ENEMY_REACH: f32 = 10.0;
+/// Momentum an asteroid needs, heading into an enemy, to crush it.
+const CRUSH_MOMENTUM: f32 = 600.0;
+/// Impulse pushing a blocked enemy off an asteroid.
+const BLOCK_IMPULSE: f32 = 20.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AsteroidSize {
+    Small,
+    Medium,
+    Large,
+}
+
+pub const ASTEROID_SIZES: [AsteroidSize; 3] = [
+    AsteroidSize::Small,
+    AsteroidSize::Medium,
+    AsteroidSize::Large,
+];
+
+impl AsteroidSize {
+    pub fn radius(&self) -> f32 {
+        match self {
+            AsteroidSize::Small => 8.0,
+            AsteroidSize::Medium => 14.0,
+            AsteroidSize::Large => 22.0,
+        }
+    }
+
+    pub fn mass(&self) -> f32 {
+        match self {
+            AsteroidSize::Small => 5.0,
+            AsteroidSize::Medium => 15.0,
+            AsteroidSize::Large => 40.0,
+        }
+    }
+
+    /// What an asteroid of this size breaks into, if anything.
+    fn smaller(&self) -> Option<AsteroidSize> {
+        match self {
+            AsteroidSize::Small => None,
+            AsteroidSize::Medium => Some(AsteroidSize::Small),
+            AsteroidSize::Large => Some(AsteroidSize::Medium),
+        }
+    }
+}
+
+#[derive(Component, Clone)]
+pub struct Asteroid {
+    pub size: AsteroidSize,
+    /// Whether it breaks in two when caught in an explosion.
+    pub splits: bool,
+}
+
+pub fn spawn_asteroid(
+    commands: &mut Commands,
+    pos: Vec3,
+    velocity: Velocity,
+    asteroid: Asteroid,
+) -> Entity {
+    commands
+        .spawn((
+            TransformBundle::from_transform(Transform::from_translation(pos)),
+            RigidBody::Dynamic,
+            Collider::ball(asteroid.size.radius()),
+            CollisionGroups::new(
+                Group::from_bits(CollisionMemberships::Debris as u32).unwrap(),
+                Group::from_bits(CollisionFilters::Debris as u32).unwrap(),
+            ),
+            ColliderMassProperties::Mass(asteroid.size.mass()),
+            Restitution::coefficient(0.8),
+            Damping {
+                linear_damping: 0.0,
+                angular_damping: 0.2,
+            },
+            Explodee,
+            ExternalForce {
+                force: Vec2::ZERO,
+                torque: 0.0,
+            },
+            ExternalImpulse {
+                impulse: Vec2::ZERO,
+                torque_impulse: 0.0,
+            },
+            velocity,
+            asteroid,
+        ))
+        .id()
+}
+
+fn spawn_asteroids(
+    mut commands: Commands,
+    rule: Res<AsteroidRule>,
+    game_seed: Res<GameSeed>,
+    mode: Res<PlayerMode>,
+    friend: Res<FriendStartingPoint>,
+    rival: Res<RivalStartingPoint>,
+    foe: Res<FoeStartingPoint>,
+) {
+    if !rule.enabled {
+        return;
+    }
+    let mut rng: Pcg64 = Seeder::from(("asteroids", &game_seed.0)).make_rng();
+    let mut bases = vec![friend.0, foe.0];
+    if mode.teams() > 1 {
+        bases.push(rival.0);
+    }
+    // Settles for fewer asteroids if the bases leave too little room.
+    let mut placed = 0;
+    for _ in 0..MAX_DRAWS {
+        if placed >= rule.count {
+            break;
+        }
+        let size = *ASTEROID_SIZES.choose(&mut rng).unwrap();
+        let pos = Vec3::new(
+            rng.gen_range(-DRIFT_EDGE..DRIFT_EDGE),
+            rng.gen_range(-DRIFT_EDGE..DRIFT_EDGE),
+            0.0,
+        );
+        let clearance = START_RADIUS + BASE_CLEARANCE + size.radius();
+        if bases.iter().any(|base| base.distance(pos) < clearance) {
+            continue;
+        }
+        let heading = rng.gen_range(0.0..TAU);
+        let velocity = Velocity {
+            linvel: rng.gen_range(ASTEROID_SPEED) * Vec2::from_angle(heading),
+            angvel: rng.gen_range(-1.0..1.0),
+        };
+        let splits = rng.gen_bool(rule.split_chance);
+        spawn_asteroid(&mut commands, pos, velocity, Asteroid { size, splits });
+        placed += 1;
+    }
+}
+
+fn bounce_asteroids(mut asteroids: Query<(&Transform, &mut Velocity), With<Asteroid>>) {
+    for (trans, mut velocity) in asteroids.iter_mut() {
+        let pos = trans.translation;
+        if pos.x.abs() > DRIFT_EDGE && pos.x * velocity.linvel.x > 0.0 {
+            velocity.linvel.x = -velocity.linvel.x;
+        }
+        if pos.y.abs() > DRIFT_EDGE && pos.y * velocity.linvel.y > 0.0 {
+            velocity.linvel.y = -velocity.linvel.y;
+        }
+    }
+}
+
+/// Splits asteroids caught in a blast into two smaller ones flying apart.
+fn split_asteroids(
+    mut commands: Commands,
+    explosions: Query<(&Transform, &Explosion), Added<Explosion>>,
+    asteroids: Query<(Entity, &Transform, &Velocity, &Asteroid)>,
+) {
+    for (entity, trans, velocity, asteroid) in asteroids.iter() {
+        let Some(smaller) = asteroid.size.smaller().filter(|_| asteroid.splits) else {
+            continue;
+        };
+        let pos = trans.translation;
+        let Some((blast, _)) = explosions.iter().find(|(blast, explosion)| {
+            blast.translation.distance(pos) <= explosion.radius + asteroid.size.radius()
+        }) else {
+            continue;
+        };
+        commands.entity(entity).despawn();
+        let away = (pos - blast.translation).truncate().normalize_or_zero();
+        let apart = away.perp();
+        for side in [-1.0, 1.0] {
+            let offset = side * smaller.radius() * apart;
+            spawn_asteroid(
+                &mut commands,
+                pos + offset.extend(0.0),
+                Velocity {
+                    linvel: velocity.linvel + SPLIT_SPEED * (away + side * apart),
+                    angvel: side * velocity.angvel,
+                },
+                Asteroid {
+                    size: smaller,
+                    splits: true,
+                },
+            );
+        }
+    }
+}
+
+/// Holds enemies off asteroids, crushing any hit hard enough.
+fn block_enemies(
+    foe_start: Res<FoeStartingPoint>,
+    asteroids: Query<(&Transform, &Velocity, &Asteroid), Without<IsEnemy>>,
+    mut enemies: Query<
+        (&mut Transform, &mut Velocity, &mut ExternalImpulse),
+        (With<IsEnemy>, Without<Asteroid>),
+    >,
+) {
+    for (mut trans, mut velocity, mut impulse) in enemies.iter_mut() {
+        for (rock, rock_velocity, asteroid) in asteroids.iter() {
+            let offset = (trans.translation - rock.translation).truncate();
+            if offset.length() > asteroid.size.radius() + ENEMY_REACH {
+                continue;
+            }
+            let away = offset.normalize_or_zero();
+            // Only the rock's own momentum crushes; an enemy can't ram itself flat.
+            if rock_velocity.linvel.dot(away) * asteroid.size.mass() >= CRUSH_MOMENTUM {
+                trans.translation = foe_start.0;
+                *velocity = Velocity::zero();
+                break;
+            }
+            let inward = velocity.linvel.dot(away).min(0.0);
+            velocity.linvel -= inward * away;
+            impulse.impulse += BLOCK_IMPULSE * away;
+        }
+    }
+}
+
+fn despawn_asteroids(mut commands: Commands, asteroids: Query<Entity, With<Asteroid>>) {
+    for asteroid in asteroids.iter() {
+        commands.entity(asteroid).despawn();
+    }
+}
@@ -0,0 +1,142 @@
+//! What a friendly base does for the ships that return to it.
+use crate::{
+    spawn_torpedo, BaseConfig, BaseReached, Dormant, GameState, GameTime, IsBase, IsEnemy, Team,
+    Torpedo, TORPEDO_EXPLOSION, TORPEDO_FUSE,
+};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+pub struct BasePlugin;
+
+impl Plugin for BasePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Game)
+                .with_system(raise_shields.after("ship_events").label("shields"))
+                .with_system(repel_enemies.after("shields"))
+                .with_system(fire_turrets),
+        )
+        .add_system_set(SystemSet::on_enter(GameState::Victory).with_system(despawn_shields))
+        .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(despawn_shields));
+    }
+}
+
+/// How far from the base's centre turret torpedoes are launched.
+const TURRET_MUZZLE: f32 = 20.0;
+const TURRET_LAUNCH_SPEED: f32 = 100.0;
+
+/// The zone around a base that the enemy is kept out of for a while.
+#[derive(Component, Clone)]
+pub struct BaseShield {
+    pub radius: f32,
+    pub timer: Timer,
+}
+
+/// A base turret and the time until it can fire again.
+#[derive(Component, Clone)]
+pub struct BaseTurret(pub Timer);
+
+impl BaseTurret {
+    pub fn new(config: &BaseConfig) -> BaseTurret {
+        BaseTurret(Timer::from_seconds(config.turret_seconds, TimerMode::Once))
+    }
+}
+
+/// Each return raises the team's shield, or starts its time again.
+fn raise_shields(
+    mut commands: Commands,
+    config: Res<BaseConfig>,
+    bases: Query<(&Transform, &Team), With<IsBase>>,
+    mut shields: Query<(&mut BaseShield, &Team)>,
+    mut reached: EventReader<BaseReached>,
+) {
+    for BaseReached(team) in reached.iter() {
+        if config.shield_seconds <= 0.0 {
+            continue;
+        }
+        let timer = Timer::from_seconds(config.shield_seconds, TimerMode::Once);
+        if let Some((mut shield, _)) = shields.iter_mut().find(|(_, t)| t.0 == *team) {
+            shield.timer = timer;
+            continue;
+        }
+        let Some((trans, _)) = bases.iter().find(|(_, t)| t.0 == *team) else {
+            continue;
+        };
+        commands.spawn((
+            TransformBundle::from_transform(*trans),
+            BaseShield {
+                radius: config.shield_radius,
+                timer,
+            },
+            Team(*team),
+        ));
+    }
+}
+
+/// Stops enemies heading into a shield and pushes them back out.
+fn repel_enemies(
+    mut commands: Commands,
+    time: GameTime,
+    config: Res<BaseConfig>,
+    mut shields: Query<(Entity, &Transform, &mut BaseShield), Without<IsEnemy>>,
+    mut enemies: Query<(&Transform, &mut Velocity, &mut ExternalImpulse), With<IsEnemy>>,
+) {
+    for (entity, shield_trans, mut shield) in shields.iter_mut() {
+        if shield.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let centre = shield_trans.translation.truncate();
+        for (trans, mut velocity, mut impulse) in enemies.iter_mut() {
+            let offset = trans.translation.truncate() - centre;
+            if offset.length() > shield.radius {
+                continue;
+            }
+            let away = offset.normalize_or_zero();
+            let inward = velocity.linvel.dot(away).min(0.0);
+            velocity.linvel -= inward * away;
+            impulse.impulse += config.repel_impulse * away;
+        }
+    }
+}
+
+/// Turrets fire at the nearest enemy in range whenever they are ready.
+fn fire_turrets(
+    mut commands: Commands,
+    time: GameTime,
+    config: Res<BaseConfig>,
+    mut turrets: Query<(&Transform, &Team, &mut BaseTurret), With<IsBase>>,
+    enemies: Query<&Transform, (With<IsEnemy>, Without<Dormant>)>,
+) {
+    for (base, team, mut turret) in turrets.iter_mut() {
+        if !turret.0.tick(time.delta()).finished() {
+            continue;
+        }
+        let centre = base.translation.truncate();
+        let target = enemies
+            .iter()
+            .map(|enemy| enemy.translation.truncate() - centre)
+            .filter(|offset| offset.length() <= config.turret_radius)
+            .min_by(|a, b| a.length().total_cmp(&b.length()));
+        let Some(offset) = target else {
+            continue;
+        };
+        let aim = offset.normalize_or_zero();
+        let trans = Transform::from_translation((centre + TURRET_MUZZLE * aim).extend(0.0))
+            .with_rotation(Quat::from_rotation_z(aim.y.atan2(aim.x)));
+        spawn_torpedo(
+            &mut commands,
+            trans,
+            Velocity::linear(TURRET_LAUNCH_SPEED * aim),
+            Some(*team),
+            Torpedo::new(TORPEDO_FUSE, TORPEDO_EXPLOSION),
+        );
+        turret.0.reset();
+    }
+}
+
+fn despawn_shields(mut commands: Commands, shields: Query<Entity, With<BaseShield>>) {
+    for shield in shields.iter() {
+        commands.entity(shield).despawn();
+    }
+}
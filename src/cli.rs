@@ -0,0 +1,245 @@
+//! Command-line flags for native builds, for scripting and benchmarking.
+use crate::{AppState, Difficulty, GameMode, GameSeed, GameState, Level, Score};
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use std::path::PathBuf;
+use std::time::Instant;
+
+pub const USAGE: &str = "Usage: GlodGET [options]
+
+  --seed <seed>            play the map for this seed
+  --difficulty <name>      easy, normal or hard
+  --game-mode <name>       classic, time-attack, score-attack or survival
+  --level <file>           play a hand-made level
+  --replay <file>          play back a recorded game
+  --record <file>          record the inputs of each game
+  --ghost <file>           race a ghost saved by another player
+  --headless               run without a window
+  --ticks <n>              quit after n frames and print a summary
+  --window-size <w>x<h>    window size in pixels
+  --host <port>            host an online versus game
+  --join <addr:port>       join an online versus game
+  --net-latency <ms>       simulated network latency
+  --net-loss <percent>     simulated packet loss";
+
+#[derive(Resource, Default, Clone, Debug)]
+pub struct Cli {
+    pub seed: Option<String>,
+    pub difficulty: Option<Difficulty>,
+    pub game_mode: Option<GameMode>,
+    pub level: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+    pub ghost: Option<PathBuf>,
+    pub headless: bool,
+    pub ticks: Option<u64>,
+    pub window_size: Option<(f32, f32)>,
+    pub host: Option<u16>,
+    pub join: Option<String>,
+    pub net_latency: u64,
+    pub net_loss: f32,
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+impl Cli {
+    pub fn from_args() -> Result<Cli, String> {
+        Cli::parse(std::env::args().skip(1))
+    }
+
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Cli, String> {
+        let mut cli = Cli::default();
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            if flag == "--headless" {
+                cli.headless = true;
+                continue;
+            }
+            if flag == "--help" || flag == "-h" {
+                return Err(String::new());
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| format!("missing value for {}", flag))?;
+            match flag.as_str() {
+                "--seed" => {
+                    if !GameSeed::is_valid(&value) {
+                        return Err(format!("invalid seed: {}", value));
+                    }
+                    cli.seed = Some(value)
+                }
+                "--difficulty" => {
+                    cli.difficulty = Some(
+                        Difficulty::from_name(&value)
+                            .ok_or_else(|| format!("unknown difficulty: {}", value))?,
+                    )
+                }
+                "--game-mode" => {
+                    cli.game_mode = Some(
+                        GameMode::from_name(&value)
+                            .ok_or_else(|| format!("unknown game mode: {}", value))?,
+                    )
+                }
+                "--level" => cli.level = Some(value.into()),
+                "--replay" => cli.replay = Some(value.into()),
+                "--record" => cli.record = Some(value.into()),
+                "--ghost" => cli.ghost = Some(value.into()),
+                "--ticks" => cli.ticks = Some(parse(&flag, &value)?),
+                "--window-size" => {
+                    let (width, height) = value.split_once('x').ok_or_else(|| {
+                        format!("window size should look like 800x600: {}", value)
+                    })?;
+                    cli.window_size = Some((parse(&flag, width)?, parse(&flag, height)?));
+                }
+                "--host" => cli.host = Some(parse(&flag, &value)?),
+                "--join" => cli.join = Some(value),
+                "--net-latency" => cli.net_latency = parse(&flag, &value)?,
+                "--net-loss" => cli.net_loss = parse(&flag, &value)?,
+                _ => return Err(format!("unknown option: {}", flag)),
+            }
+        }
+        Ok(cli)
+    }
+
+    /// Parses the process arguments, exiting with the usage text if they are wrong.
+    pub fn from_args_or_exit() -> Cli {
+        Cli::from_args().unwrap_or_else(|err| {
+            if err.is_empty() {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            exit_with(&format!("{}\n\n{}", err, USAGE))
+        })
+    }
+
+    /// Whether to go straight into a game rather than showing the main menu.
+    pub fn skips_menus(&self) -> bool {
+        self.seed.is_some()
+            || self.level.is_some()
+            || self.replay.is_some()
+            || self.ghost.is_some()
+            || self.headless
+    }
+
+    /// Whether the physics must advance by the same step every frame.
+    pub fn needs_fixed_timestep(&self) -> bool {
+        self.replay.is_some()
+            || self.record.is_some()
+            || self.headless
+            || self.host.is_some()
+            || self.join.is_some()
+    }
+}
+
+/// Reports a problem with the command line and quits.
+pub fn exit_with(err: &str) -> ! {
+    eprintln!("{}", err);
+    std::process::exit(2)
+}
+
+pub struct CliPlugin;
+
+impl Plugin for CliPlugin {
+    fn build(&self, app: &mut App) {
+        let cli = app.world.resource::<Cli>().clone();
+        if let Some(path) = &cli.level {
+            app.insert_resource(Level::load(path).unwrap_or_else(|err| exit_with(&err)));
+        }
+        app.add_startup_system(apply_cli);
+        if cli.ticks.is_some() {
+            app.add_system(count_ticks);
+        }
+    }
+}
+
+fn apply_cli(
+    cli: Res<Cli>,
+    mut game_seed: ResMut<GameSeed>,
+    mut difficulty: ResMut<Difficulty>,
+    mut game_mode: ResMut<GameMode>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    if let Some(seed) = &cli.seed {
+        *game_seed = GameSeed(seed.clone());
+    }
+    if let Some(chosen) = cli.difficulty {
+        *difficulty = chosen;
+    }
+    if let Some(chosen) = cli.game_mode {
+        *game_mode = chosen;
+    }
+    if cli.skips_menus() {
+        app_state.set(AppState::InGame).unwrap();
+    }
+}
+
+fn count_ticks(
+    cli: Res<Cli>,
+    mut ticks: Local<u64>,
+    mut started: Local<Option<Instant>>,
+    game_state: Res<State<GameState>>,
+    score: Res<Score>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let started = *started.get_or_insert_with(Instant::now);
+    *ticks += 1;
+    if Some(*ticks) == cli.ticks {
+        println!(
+            "ticks: {} elapsed: {:.3}s state: {:?} score: {:?}",
+            *ticks,
+            started.elapsed().as_secs_f64(),
+            game_state.current(),
+            score.0
+        );
+        exit.send(AppExit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cli(line: &str) -> Result<Cli, String> {
+        Cli::parse(line.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn parse_reads_each_flag() {
+        let cli = cli("--seed abc-1 --difficulty hard --ticks 600 --window-size 800x600").unwrap();
+        assert_eq!(cli.seed.as_deref(), Some("abc-1"));
+        assert_eq!(cli.difficulty, Some(Difficulty::Hard));
+        assert_eq!(cli.ticks, Some(600));
+        assert_eq!(cli.window_size, Some((800.0, 600.0)));
+    }
+
+    #[test]
+    fn only_flags_that_start_a_game_skip_the_menus() {
+        assert!(!cli("").unwrap().skips_menus());
+        assert!(!cli("--record run.txt").unwrap().skips_menus());
+        assert!(cli("--level maze.txt").unwrap().skips_menus());
+        assert!(cli("--replay run.txt").unwrap().skips_menus());
+        assert!(cli("--headless").unwrap().skips_menus());
+    }
+
+    #[test]
+    fn recording_and_network_play_fix_the_timestep() {
+        assert!(!cli("--seed abc-1").unwrap().needs_fixed_timestep());
+        assert!(cli("--record run.txt").unwrap().needs_fixed_timestep());
+        assert!(cli("--join 127.0.0.1:7000").unwrap().needs_fixed_timestep());
+    }
+
+    #[test]
+    fn parse_rejects_bad_arguments() {
+        assert!(cli("--bogus 1").is_err());
+        assert!(cli("--seed").is_err());
+        assert!(cli("--ticks many").is_err());
+        assert!(cli("--window-size 800").is_err());
+        assert!(cli("--difficulty impossible").is_err());
+        assert!(cli("--seed a,b").is_err());
+        assert_eq!(cli("-h").unwrap_err(), "");
+    }
+}
@@ -0,0 +1,163 @@
+//! Waves of enemies sent after the first release, harder with more glod and time.
+//!
+//! The waves are drawn from their own generator seeded by `GameSeed`, so the
+//! same seed always brings the same archetypes in the same order.
+use crate::{
+    spawn_enemy, Cargo, DirectorRule, EnemyState, FoeStartingPoint, ForceProfile, GameMode,
+    GameSeed, GameState, IsEnemy, RoundClock, Score, UNIT_FORCE_PROFILE, UNIT_MASS,
+};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::prelude::*;
+use rand_pcg::Pcg64;
+use rand_seeder::Seeder;
+use std::f32::consts::TAU;
+
+pub struct DirectorPlugin;
+
+impl Plugin for DirectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WaveDirector::new(&GameSeed::default()))
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(reset_director))
+            .add_system_set(
+                SystemSet::on_update(GameState::Game).with_system(direct_waves.after("release")),
+            );
+    }
+}
+
+const WAVE_SPREAD: f32 = 30.0;
+/// Waves grow by one enemy every this many waves.
+const WAVES_PER_EXTRA_ENEMY: u32 = 3;
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum EnemyArchetype {
+    /// The original enemy.
+    #[default]
+    Chaser,
+    /// Light and quick, but turns wide.
+    Sprinter,
+    /// Slow and heavy, hard to push away.
+    Brute,
+}
+
+pub const ENEMY_ARCHETYPES: [EnemyArchetype; 3] = [
+    EnemyArchetype::Chaser,
+    EnemyArchetype::Sprinter,
+    EnemyArchetype::Brute,
+];
+
+impl EnemyArchetype {
+    pub fn force_profile(&self) -> ForceProfile {
+        let (forward, torque) = match self {
+            EnemyArchetype::Chaser => (1.0, 1.0),
+            EnemyArchetype::Sprinter => (1.2, 0.6),
+            EnemyArchetype::Brute => (1.5, 1.2),
+        };
+        ForceProfile {
+            forward: UNIT_FORCE_PROFILE.forward * forward,
+            torque: UNIT_FORCE_PROFILE.torque * torque,
+        }
+    }
+
+    pub fn mass(&self) -> f32 {
+        match self {
+            EnemyArchetype::Chaser => UNIT_MASS,
+            EnemyArchetype::Sprinter => UNIT_MASS * 0.6,
+            EnemyArchetype::Brute => UNIT_MASS * 3.0,
+        }
+    }
+
+    /// How often the archetype turns up in a wave, zero until it is unlocked.
+    fn weight(&self, wave: u32) -> u32 {
+        match self {
+            EnemyArchetype::Chaser => 3,
+            EnemyArchetype::Sprinter if wave >= 2 => 2,
+            EnemyArchetype::Brute if wave >= 3 => 1,
+            _ => 0,
+        }
+    }
+}
+
+pub fn spawn_archetype(commands: &mut Commands, pos: Vec3, archetype: EnemyArchetype) -> Entity {
+    let enemy = spawn_enemy(commands, pos);
+    commands
+        .entity(enemy)
+        .insert((archetype, ColliderMassProperties::Mass(archetype.mass())));
+    enemy
+}
+
+#[derive(Resource, Clone)]
+pub struct WaveDirector {
+    rng: Pcg64,
+    /// Waves sent so far this game.
+    pub waves: u32,
+    /// Round clock time of the next wave, once the enemy is released.
+    pub next_wave: Option<f32>,
+    released_at: f32,
+}
+
+impl WaveDirector {
+    fn new(seed: &GameSeed) -> WaveDirector {
+        WaveDirector {
+            rng: Seeder::from(("waves", &seed.0)).make_rng(),
+            waves: 0,
+            next_wave: None,
+            released_at: 0.0,
+        }
+    }
+
+    fn pick_archetype(&mut self) -> EnemyArchetype {
+        let wave = self.waves;
+        *ENEMY_ARCHETYPES
+            .choose_weighted(&mut self.rng, |archetype| archetype.weight(wave))
+            .unwrap()
+    }
+}
+
+fn reset_director(game_seed: Res<GameSeed>, mut director: ResMut<WaveDirector>) {
+    *director = WaveDirector::new(&game_seed);
+}
+
+fn direct_waves(
+    mut commands: Commands,
+    rule: Res<DirectorRule>,
+    game_mode: Res<GameMode>,
+    enemy_state: Res<State<EnemyState>>,
+    clock: Res<RoundClock>,
+    score: Res<Score>,
+    cargo: Query<&Cargo>,
+    foe_start: Res<FoeStartingPoint>,
+    enemies: Query<(), With<IsEnemy>>,
+    mut director: ResMut<WaveDirector>,
+) {
+    // Survival already has waves of its own.
+    if !rule.enabled || *game_mode == GameMode::Survival {
+        return;
+    }
+    if *enemy_state.current() != EnemyState::Released {
+        return;
+    }
+    let now = clock.seconds();
+    // Glod still being carried home counts as much as glod already banked.
+    let glod = score.total() + cargo.iter().map(Cargo::value).sum::<u32>();
+    let Some(next_wave) = director.next_wave else {
+        director.released_at = now;
+        director.next_wave = Some(now + rule.wave_interval(0.0, glod));
+        return;
+    };
+    if now < next_wave {
+        return;
+    }
+    director.waves += 1;
+    let elapsed = now - director.released_at;
+    director.next_wave = Some(now + rule.wave_interval(elapsed, glod));
+
+    let room = rule.max_enemies.saturating_sub(enemies.iter().count());
+    let size = usize::min(1 + (director.waves / WAVES_PER_EXTRA_ENEMY) as usize, room);
+    for i in 0..size {
+        let angle = TAU * i as f32 / size as f32;
+        let offset = WAVE_SPREAD * Vec3::new(angle.cos(), angle.sin(), 0.0);
+        let archetype = director.pick_archetype();
+        spawn_archetype(&mut commands, foe_start.0 + offset, archetype);
+    }
+}
@@ -0,0 +1,186 @@
+//! Patterns for laying out glod across the arena.
+use bevy::prelude::*;
+use rand::prelude::*;
+use rand_pcg::Pcg64;
+use std::f32::consts::TAU;
+
+/// Proposes glod positions; the map constraints may still reject them.
+pub trait GlodDistribution {
+    fn sample(&mut self, rng: &mut Pcg64) -> Vec3;
+
+    /// Velocity a glod at `pos` starts with.
+    fn drift(&mut self, _rng: &mut Pcg64, _pos: Vec3) -> Vec2 {
+        Vec2::ZERO
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum GlodPattern {
+    Uniform,
+    Veins,
+    Rings,
+    Spiral,
+    Gradient,
+    Drifting,
+}
+
+pub const GLOD_PATTERNS: [GlodPattern; 6] = [
+    GlodPattern::Uniform,
+    GlodPattern::Veins,
+    GlodPattern::Rings,
+    GlodPattern::Spiral,
+    GlodPattern::Gradient,
+    GlodPattern::Drifting,
+];
+
+impl GlodPattern {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GlodPattern::Uniform => "Scattered",
+            GlodPattern::Veins => "Veins",
+            GlodPattern::Rings => "Rings",
+            GlodPattern::Spiral => "Spiral",
+            GlodPattern::Gradient => "Gradient",
+            GlodPattern::Drifting => "Drifting",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GlodPattern> {
+        GLOD_PATTERNS
+            .into_iter()
+            .find(|pattern| pattern.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Builds the distribution for a map whose enemy base is at `foe`, keeping
+    /// glod within `edge` of the centre.
+    pub fn distribution(&self, rng: &mut Pcg64, foe: Vec3, edge: f32) -> Box<dyn GlodDistribution> {
+        match self {
+            GlodPattern::Uniform => Box::new(Uniform { edge }),
+            GlodPattern::Veins => Box::new(Veins::new(rng, edge)),
+            GlodPattern::Rings => Box::new(Rings { centre: foe }),
+            GlodPattern::Spiral => Box::new(Spiral {
+                phase: rng.gen_range(0.0..TAU),
+                turns: rng.gen_range(2.0..3.5),
+                reach: edge,
+            }),
+            GlodPattern::Gradient => Box::new(Gradient { edge, danger: foe }),
+            GlodPattern::Drifting => Box::new(Drifting {
+                scatter: Uniform { edge },
+            }),
+        }
+    }
+}
+
+struct Uniform {
+    edge: f32,
+}
+
+impl GlodDistribution for Uniform {
+    fn sample(&mut self, rng: &mut Pcg64) -> Vec3 {
+        Vec3::new(
+            rng.gen_range(-self.edge..self.edge),
+            rng.gen_range(-self.edge..self.edge),
+            0.0,
+        )
+    }
+}
+
+const VEIN_COUNT: usize = 5;
+const VEIN_LENGTH: f32 = 70.0;
+const VEIN_WIDTH: f32 = 12.0;
+
+/// Short straight seams of glod.
+struct Veins {
+    veins: Vec<(Vec2, Vec2)>,
+}
+
+impl Veins {
+    fn new(rng: &mut Pcg64, edge: f32) -> Veins {
+        let veins = (0..VEIN_COUNT)
+            .map(|_| {
+                let centre = Vec2::new(rng.gen_range(-edge..edge), rng.gen_range(-edge..edge));
+                let angle: f32 = rng.gen_range(0.0..TAU);
+                (centre, Vec2::new(angle.cos(), angle.sin()))
+            })
+            .collect();
+        Veins { veins }
+    }
+}
+
+impl GlodDistribution for Veins {
+    fn sample(&mut self, rng: &mut Pcg64) -> Vec3 {
+        let (centre, dir) = self.veins[rng.gen_range(0..self.veins.len())];
+        let along = rng.gen_range(-VEIN_LENGTH..VEIN_LENGTH);
+        let across = rng.gen_range(-VEIN_WIDTH..VEIN_WIDTH);
+        (centre + along * dir + across * dir.perp()).extend(0.0)
+    }
+}
+
+const RING_RADII: [f32; 3] = [110.0, 170.0, 230.0];
+
+/// Concentric rings around the enemy base, so glod leads into danger.
+struct Rings {
+    centre: Vec3,
+}
+
+impl GlodDistribution for Rings {
+    fn sample(&mut self, rng: &mut Pcg64) -> Vec3 {
+        let radius = RING_RADII[rng.gen_range(0..RING_RADII.len())] + rng.gen_range(-6.0..6.0);
+        let angle: f32 = rng.gen_range(0.0..TAU);
+        self.centre + radius * Vec3::new(angle.cos(), angle.sin(), 0.0)
+    }
+}
+
+/// An arm winding out from the middle of the arena.
+struct Spiral {
+    phase: f32,
+    turns: f32,
+    reach: f32,
+}
+
+impl GlodDistribution for Spiral {
+    fn sample(&mut self, rng: &mut Pcg64) -> Vec3 {
+        let t: f32 = rng.gen_range(0.0..1.0);
+        let angle = self.phase + t * self.turns * TAU;
+        let radius = 20.0 + t * (self.reach - 20.0);
+        let jitter = Vec3::new(rng.gen_range(-8.0..8.0), rng.gen_range(-8.0..8.0), 0.0);
+        radius * Vec3::new(angle.cos(), angle.sin(), 0.0) + jitter
+    }
+}
+
+/// Sparse near home, growing denser towards the enemy base.
+struct Gradient {
+    edge: f32,
+    danger: Vec3,
+}
+
+impl GlodDistribution for Gradient {
+    fn sample(&mut self, rng: &mut Pcg64) -> Vec3 {
+        let farthest = 2.0 * 2.0_f32.sqrt() * self.edge;
+        loop {
+            let pos = Uniform { edge: self.edge }.sample(rng);
+            let closeness = 1.0 - pos.distance(self.danger) / farthest;
+            if rng.gen::<f32>() < closeness * closeness {
+                return pos;
+            }
+        }
+    }
+}
+
+const DRIFT_SPEED: std::ops::Range<f32> = 5.0..20.0;
+
+/// Scattered glod that slowly floats about.
+struct Drifting {
+    scatter: Uniform,
+}
+
+impl GlodDistribution for Drifting {
+    fn sample(&mut self, rng: &mut Pcg64) -> Vec3 {
+        self.scatter.sample(rng)
+    }
+
+    fn drift(&mut self, rng: &mut Pcg64, _pos: Vec3) -> Vec2 {
+        let angle: f32 = rng.gen_range(0.0..TAU);
+        rng.gen_range(DRIFT_SPEED) * Vec2::new(angle.cos(), angle.sin())
+    }
+}
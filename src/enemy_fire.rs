@@ -0,0 +1,136 @@
+//! Enemies that launch torpedoes of their own.
+//!
+//! An enemy fires when a ship sits in a narrow cone ahead of it and within
+//! range, then reloads. Each launch kicks the enemy back and stops it chasing
+//! for a moment, so a shot taken is ground given up.
+use crate::{
+    spawn_torpedo, Body, CollisionFilters, CollisionMemberships, Dormant, EnemyFireRule,
+    EnemyState, Explosion, GameState, GameTime, IsEnemy, Playable, Torpedo,
+};
+use bevy::prelude::*;
+use bevy_rapier2d::geometry::Group;
+use bevy_rapier2d::prelude::*;
+use std::time::Duration;
+
+pub struct EnemyFirePlugin;
+
+impl Plugin for EnemyFirePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Game)
+                .with_system(arm_enemies)
+                .with_system(enemy_fire.after("release")),
+        );
+    }
+}
+
+pub const ENEMY_TORPEDO_FUSE: Duration = Duration::from_millis(2000);
+pub const ENEMY_TORPEDO_EXPLOSION: Explosion = Explosion {
+    radius: 40.0,
+    origin_impulse: 2500.0,
+};
+/// How far ahead of an enemy's centre its torpedoes are launched, clear of its nose.
+const ENEMY_MUZZLE: f32 = 20.0;
+const RECOIL_IMPULSE: f32 = 80.0;
+
+/// A torpedo fired by the enemy.
+#[derive(Component, Clone)]
+pub struct EnemyOrdnance;
+
+#[derive(Component, Clone)]
+pub struct EnemyLauncher {
+    reload: Timer,
+    recoil: Timer,
+}
+
+impl EnemyLauncher {
+    fn new(rule: &EnemyFireRule) -> EnemyLauncher {
+        let mut recoil = Timer::from_seconds(rule.recoil_seconds, TimerMode::Once);
+        recoil.tick(recoil.duration());
+        EnemyLauncher {
+            reload: Timer::from_seconds(rule.cooldown_seconds, TimerMode::Once),
+            recoil,
+        }
+    }
+
+    /// Share of its thrust an enemy puts into chasing, none while recoiling.
+    pub fn thrust_factor(&self) -> f32 {
+        if self.recoil.finished() {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+fn arm_enemies(
+    mut commands: Commands,
+    rule: Res<EnemyFireRule>,
+    enemies: Query<Entity, Added<IsEnemy>>,
+) {
+    if !rule.enabled {
+        return;
+    }
+    for enemy in enemies.iter() {
+        commands.entity(enemy).insert(EnemyLauncher::new(&rule));
+    }
+}
+
+fn enemy_fire(
+    mut commands: Commands,
+    time: GameTime,
+    rule: Res<EnemyFireRule>,
+    enemy_state: Res<State<EnemyState>>,
+    ships: Query<(&Transform, Option<&Velocity>), With<Playable>>,
+    mut enemies: Query<
+        (
+            &Transform,
+            Option<&Velocity>,
+            &mut EnemyLauncher,
+            &mut ExternalImpulse,
+        ),
+        (With<IsEnemy>, Without<Dormant>),
+    >,
+) {
+    if *enemy_state.current() != EnemyState::Released {
+        return;
+    }
+    for (trans, vel, mut launcher, mut impulse) in enemies.iter_mut() {
+        launcher.recoil.tick(time.delta());
+        if !launcher.reload.tick(time.delta()).finished() {
+            continue;
+        }
+        let enemy = Body::new((trans, vel));
+        let Some(target) = ships
+            .iter()
+            .map(Body::new)
+            .min_by(|a, b| enemy.distance(a).total_cmp(&enemy.distance(b)))
+        else {
+            continue;
+        };
+        let (_, angle) = enemy.bearing_of(&target);
+        let distance = enemy.distance(&target);
+        if angle > rule.cone || distance > rule.range || distance < rule.min_range {
+            continue;
+        }
+
+        let muzzle = *trans * Transform::from_translation(Vec3::new(ENEMY_MUZZLE, 0.0, 0.0));
+        let torpedo = spawn_torpedo(
+            &mut commands,
+            muzzle,
+            Velocity::linear(vel.map_or(Vec2::ZERO, |vel| vel.linvel)),
+            None,
+            Torpedo::new(ENEMY_TORPEDO_FUSE, ENEMY_TORPEDO_EXPLOSION),
+        );
+        commands.entity(torpedo).insert((
+            EnemyOrdnance,
+            CollisionGroups::new(
+                Group::from_bits(CollisionMemberships::EnemyOrdnance as u32).unwrap(),
+                Group::from_bits(CollisionFilters::EnemyOrdnance as u32).unwrap(),
+            ),
+        ));
+        impulse.impulse -= RECOIL_IMPULSE * trans.local_x().truncate();
+        launcher.reload.reset();
+        launcher.recoil.reset();
+    }
+}
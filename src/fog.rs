@@ -0,0 +1,117 @@
+//! Fog of war: explosions that light up the dark, and a sonar ping that
+//! shows everything but gives the ship away.
+use crate::{
+    release, Dormant, EnemyState, Explosion, FogRule, GameState, GameTime, IsEnemy, Playable,
+    ShipInput,
+};
+use bevy::prelude::*;
+
+pub struct FogPlugin;
+
+impl Plugin for FogPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Sonar::new(&FogRule::default()))
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(reset_sonar))
+            .add_system_set(
+                SystemSet::on_update(GameState::Game)
+                    .with_system(light_flares)
+                    .with_system(fade_flares)
+                    .with_system(sonar_ping.after("inputs").before("release")),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Victory).with_system(despawn_flares))
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(despawn_flares));
+    }
+}
+
+/// An area briefly lit up by an explosion.
+#[derive(Component, Clone)]
+pub struct Flare {
+    pub radius: f32,
+    timer: Timer,
+}
+
+#[derive(Resource, Clone)]
+pub struct Sonar {
+    reveal: Timer,
+    recharge: Timer,
+}
+
+impl Sonar {
+    fn new(rule: &FogRule) -> Sonar {
+        let mut reveal = Timer::from_seconds(rule.ping_seconds, TimerMode::Once);
+        reveal.tick(reveal.duration());
+        let mut recharge = Timer::from_seconds(rule.ping_recharge_seconds, TimerMode::Once);
+        recharge.tick(recharge.duration());
+        Sonar { reveal, recharge }
+    }
+
+    /// Whether a ping is showing the whole map.
+    pub fn revealing(&self) -> bool {
+        !self.reveal.finished()
+    }
+
+    /// Seconds until the next ping can be sent, zero once ready.
+    pub fn recharge_left(&self) -> f32 {
+        self.recharge.remaining_secs()
+    }
+}
+
+fn reset_sonar(rule: Res<FogRule>, mut sonar: ResMut<Sonar>) {
+    *sonar = Sonar::new(&rule);
+}
+
+fn light_flares(
+    mut commands: Commands,
+    rule: Res<FogRule>,
+    explosions: Query<&Transform, Added<Explosion>>,
+) {
+    if !rule.enabled {
+        return;
+    }
+    for trans in explosions.iter() {
+        commands.spawn((
+            TransformBundle::from_transform(*trans),
+            Flare {
+                radius: rule.flare_radius,
+                timer: Timer::from_seconds(rule.flare_seconds, TimerMode::Once),
+            },
+        ));
+    }
+}
+
+fn fade_flares(mut commands: Commands, time: GameTime, mut flares: Query<(Entity, &mut Flare)>) {
+    for (entity, mut flare) in flares.iter_mut() {
+        if flare.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// A ping shows the whole map for a moment, but wakes every enemy.
+fn sonar_ping(
+    mut commands: Commands,
+    time: GameTime,
+    rule: Res<FogRule>,
+    ships: Query<&ShipInput, With<Playable>>,
+    dormant: Query<Entity, (With<IsEnemy>, With<Dormant>)>,
+    mut sonar: ResMut<Sonar>,
+    mut enemy_state: ResMut<State<EnemyState>>,
+) {
+    sonar.reveal.tick(time.delta());
+    sonar.recharge.tick(time.delta());
+    if !rule.enabled || !sonar.recharge.finished() || !ships.iter().any(|input| input.ping) {
+        return;
+    }
+    sonar.reveal.reset();
+    sonar.recharge.reset();
+    release(&mut enemy_state);
+    for enemy in dormant.iter() {
+        commands.entity(enemy).remove::<Dormant>();
+    }
+}
+
+fn despawn_flares(mut commands: Commands, flares: Query<Entity, With<Flare>>) {
+    for flare in flares.iter() {
+        commands.entity(flare).despawn();
+    }
+}
@@ -0,0 +1,183 @@
+//! How each game mode is won, and the clock and enemy waves they run on.
+use crate::{
+    spawn_enemy, BaseReached, Cargo, DepositRule, EndGate, EnemyState, FoeStartingPoint, GameMode,
+    GameState, GameTime, IsGlod, Playable, PlayerMode, Score, Winner,
+};
+use bevy::prelude::*;
+use bevy::time::Stopwatch;
+use std::f32::consts::TAU;
+
+pub struct GameModePlugin;
+
+impl Plugin for GameModePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RoundClock::default())
+            .insert_resource(Wave(0))
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(reset_clock))
+            .add_system_set(
+                SystemSet::on_update(GameState::Game)
+                    .with_system(tick_clock.before("victory"))
+                    .with_system(classic_victory.label("victory").after("ship_events"))
+                    .with_system(time_attack_victory.label("victory").after("ship_events"))
+                    .with_system(score_attack_victory.label("victory").after("ship_events"))
+                    .with_system(survival_waves.after("victory")),
+            );
+    }
+}
+
+/// How long a score attack lasts.
+pub const SCORE_ATTACK_SECONDS: f32 = 60.0;
+/// Time between survival waves; wave `n` brings `n` more enemies.
+pub const SURVIVAL_WAVE_SECONDS: f32 = 20.0;
+/// Most enemies a single survival wave adds.
+const MAX_WAVE_SIZE: u32 = 6;
+const WAVE_SPREAD: f32 = 30.0;
+
+/// Time spent in the current game.
+#[derive(Resource, Clone, Default)]
+pub struct RoundClock(pub Stopwatch);
+
+impl RoundClock {
+    pub fn seconds(&self) -> f32 {
+        self.0.elapsed_secs()
+    }
+
+    pub fn millis(&self) -> u32 {
+        self.0.elapsed().as_millis() as u32
+    }
+}
+
+/// Survival waves released so far.
+#[derive(Resource, Clone)]
+pub struct Wave(pub u32);
+
+fn reset_clock(mut clock: ResMut<RoundClock>, mut wave: ResMut<Wave>) {
+    clock.0.reset();
+    *wave = Wave(0);
+}
+
+fn tick_clock(time: GameTime, mut clock: ResMut<RoundClock>) {
+    clock.0.tick(time.delta());
+}
+
+/// Team with the most glod, the first on a tie.
+fn leading_team(score: &Score, mode: &PlayerMode) -> usize {
+    (0..mode.teams())
+        .rev()
+        .max_by_key(|team| score.0[*team])
+        .unwrap_or(0)
+}
+
+/// Team ahead when `returning` gets home and ends the game; a tie goes to
+/// the team that made it back.
+fn leader_on_return(score: &Score, returning: usize) -> usize {
+    let best = score.best();
+    if score.0[returning] >= best {
+        returning
+    } else {
+        score.0.iter().position(|glod| *glod == best).unwrap_or(returning)
+    }
+}
+
+/// Ends the game in victory for `team`, unless the end has to wait for
+/// the other player's real inputs.
+pub fn win(
+    team: usize,
+    winner: &mut Winner,
+    game_state: &mut State<GameState>,
+    gate: &mut EndGate,
+) {
+    if gate.allows_end() {
+        *winner = Winner(Some(team));
+        game_state.set(GameState::Victory).unwrap();
+    }
+}
+
+/// Back at base after the enemy has been released, with enough glod banked.
+/// The first ship home ends the game, but the team with the most glod wins it.
+fn classic_victory(
+    game_mode: Res<GameMode>,
+    enemy_state: Res<State<EnemyState>>,
+    deposit: Res<DepositRule>,
+    score: Res<Score>,
+    mut reached: EventReader<BaseReached>,
+    mut winner: ResMut<Winner>,
+    mut game_state: ResMut<State<GameState>>,
+    mut gate: ResMut<EndGate>,
+) {
+    for BaseReached(team) in reached.iter() {
+        if *game_mode == GameMode::Classic
+            && *enemy_state.current() == EnemyState::Released
+            && deposit.allows_victory(score.0[*team])
+            && winner.0.is_none()
+        {
+            let leader = leader_on_return(&score, *team);
+            win(leader, &mut winner, &mut game_state, &mut gate);
+        }
+    }
+}
+
+/// Every glod collected, and banked too if glod has to be deposited.
+fn time_attack_victory(
+    game_mode: Res<GameMode>,
+    mode: Res<PlayerMode>,
+    deposit: Res<DepositRule>,
+    score: Res<Score>,
+    glods: Query<(), With<IsGlod>>,
+    cargo: Query<&Cargo, With<Playable>>,
+    mut winner: ResMut<Winner>,
+    mut game_state: ResMut<State<GameState>>,
+    mut gate: ResMut<EndGate>,
+) {
+    if *game_mode != GameMode::TimeAttack || winner.0.is_some() || !glods.is_empty() {
+        return;
+    }
+    if deposit.enabled && cargo.iter().any(|cargo| !cargo.0.is_empty()) {
+        return;
+    }
+    let leader = leading_team(&score, &mode);
+    win(leader, &mut winner, &mut game_state, &mut gate);
+}
+
+/// Still flying when the time is up.
+fn score_attack_victory(
+    game_mode: Res<GameMode>,
+    mode: Res<PlayerMode>,
+    clock: Res<RoundClock>,
+    score: Res<Score>,
+    mut winner: ResMut<Winner>,
+    mut game_state: ResMut<State<GameState>>,
+    mut gate: ResMut<EndGate>,
+) {
+    if *game_mode != GameMode::ScoreAttack || winner.0.is_some() {
+        return;
+    }
+    if clock.seconds() >= SCORE_ATTACK_SECONDS {
+        let leader = leading_team(&score, &mode);
+        win(leader, &mut winner, &mut game_state, &mut gate);
+    }
+}
+
+/// Survival can't be won, only lasted; each wave brings in more enemies.
+fn survival_waves(
+    mut commands: Commands,
+    game_mode: Res<GameMode>,
+    mode: Res<PlayerMode>,
+    clock: Res<RoundClock>,
+    foe_start: Res<FoeStartingPoint>,
+    mut wave: ResMut<Wave>,
+) {
+    if *game_mode != GameMode::Survival {
+        return;
+    }
+    let due = (clock.seconds() / SURVIVAL_WAVE_SECONDS) as u32;
+    while wave.0 < due {
+        wave.0 += 1;
+        let size = u32::min(wave.0 * mode.enemies() as u32, MAX_WAVE_SIZE);
+        for i in 0..size {
+            let angle = TAU * i as f32 / size as f32;
+            let offset = WAVE_SPREAD * Vec3::new(angle.cos(), angle.sin(), 0.0);
+            spawn_enemy(&mut commands, foe_start.0 + offset);
+        }
+    }
+}
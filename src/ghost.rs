@@ -0,0 +1,372 @@
+//! Racing a recording of the best run on a seed.
+//!
+//! The ship's position is sampled against the round clock, so a ghost plays
+//! back at the same pace whatever the frame rate. The best run for each seed
+//! and mode is kept under `ghost_<mode>_<seed>` in storage. A ghost file
+//! starts with `seed`, `mode` and `result` lines, followed by one
+//! `<millis>,<x>,<y>,<angle>` line per sample; on native builds it can be
+//! shared and raced with `--ghost`.
+use crate::storage;
+use crate::{counts, exit_with, Cli, GameMode, GameSeed, GameState, LastResult, Pilot, Playable};
+use crate::{RoundClock, Tutorial, UNIT_POINTS};
+use bevy::prelude::*;
+use bevy_rapier2d::geometry::Group;
+use bevy_rapier2d::prelude::*;
+use std::path::Path;
+
+/// Time between recorded samples.
+const GHOST_SAMPLE_MILLIS: u32 = 50;
+
+#[derive(Clone, Copy, Debug)]
+pub struct GhostSample {
+    pub millis: u32,
+    pub pos: Vec2,
+    pub angle: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct Ghost {
+    pub seed: GameSeed,
+    pub mode: GameMode,
+    /// The run's leaderboard result, to tell whether a new run beats it.
+    pub result: u32,
+    pub samples: Vec<GhostSample>,
+}
+
+impl Ghost {
+    pub fn load(path: &Path) -> Result<Ghost, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+        Ghost::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Ghost, String> {
+        let mut lines = text.lines();
+        let mut header = |key: &str| {
+            lines
+                .next()
+                .and_then(|line| line.strip_prefix(key))
+                .and_then(|rest| rest.strip_prefix(' '))
+                .map(str::to_string)
+                .ok_or_else(|| format!("ghost is missing its {} line", key))
+        };
+        let seed = header("seed")?;
+        if !GameSeed::is_valid(&seed) {
+            return Err(format!("invalid seed in ghost: {}", seed));
+        }
+        let seed = GameSeed(seed);
+        let mode = header("mode")?;
+        let mode =
+            GameMode::from_name(&mode).ok_or_else(|| format!("unknown game mode: {}", mode))?;
+        let result = header("result")?;
+        let result = result
+            .parse()
+            .map_err(|_| format!("bad ghost result: {}", result))?;
+
+        let samples = lines
+            .map(|line| {
+                let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+                let bad = || format!("bad ghost sample: {}", line);
+                let [millis, x, y, angle] = fields[..] else {
+                    return Err(bad());
+                };
+                Ok(GhostSample {
+                    millis: millis.parse().map_err(|_| bad())?,
+                    pos: Vec2::new(x.parse().map_err(|_| bad())?, y.parse().map_err(|_| bad())?),
+                    angle: angle.parse().map_err(|_| bad())?,
+                })
+            })
+            .collect::<Result<Vec<GhostSample>, String>>()?;
+        Ok(Ghost {
+            seed,
+            mode,
+            result,
+            samples,
+        })
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = format!(
+            "seed {}\nmode {}\nresult {}\n",
+            self.seed.0,
+            self.mode.key(),
+            self.result
+        );
+        for sample in &self.samples {
+            text += &format!(
+                "{},{:.1},{:.1},{:.3}\n",
+                sample.millis, sample.pos.x, sample.pos.y, sample.angle
+            );
+        }
+        text
+    }
+
+    fn storage_key(seed: &GameSeed, mode: GameMode) -> String {
+        format!("ghost_{}_{}", mode.key(), seed.0)
+    }
+
+    fn from_storage(seed: &GameSeed, mode: GameMode) -> Option<Ghost> {
+        let text = storage::load(&Ghost::storage_key(seed, mode))?;
+        Ghost::parse(&text).ok()
+    }
+
+    fn beaten_by(&self, result: u32) -> bool {
+        if self.mode.lower_is_better() {
+            result < self.result
+        } else {
+            result > self.result
+        }
+    }
+
+    /// Where the ghost is `millis` into the run, holding still once it ends.
+    fn transform_at(&self, millis: u32) -> Option<Transform> {
+        let (before, after) = match self.samples.iter().position(|s| s.millis > millis) {
+            Some(0) => (self.samples.first()?, self.samples.first()?),
+            Some(next) => (&self.samples[next - 1], &self.samples[next]),
+            None => (self.samples.last()?, self.samples.last()?),
+        };
+        let span = after.millis.saturating_sub(before.millis).max(1) as f32;
+        let t = (millis.saturating_sub(before.millis) as f32 / span).clamp(0.0, 1.0);
+        let rotation =
+            Quat::from_rotation_z(before.angle).slerp(Quat::from_rotation_z(after.angle), t);
+        Some(
+            Transform::from_translation(before.pos.lerp(after.pos, t).extend(0.0))
+                .with_rotation(rotation),
+        )
+    }
+}
+
+/// A ghost handed over on the command line, raced in place of the stored one.
+#[derive(Resource)]
+struct SharedGhost(Ghost);
+
+/// The ghost raced in the current game.
+#[derive(Resource, Default)]
+struct RaceGhost(Option<Ghost>);
+
+/// The run in progress, saved as the new ghost if it beats the old one.
+#[derive(Resource, Default)]
+struct GhostRecording(Vec<GhostSample>);
+
+/// The translucent ship replaying a ghost.
+#[derive(Component)]
+pub struct GhostShip;
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        let cli = app.world.resource::<Cli>().clone();
+        if let Some(path) = cli.ghost {
+            let ghost = Ghost::load(&path).unwrap_or_else(|err| exit_with(&err));
+            app.insert_resource(SharedGhost(ghost))
+                .add_startup_system(apply_ghost_settings);
+        }
+        app.insert_resource(RaceGhost::default())
+            .insert_resource(GhostRecording::default())
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(start_race))
+            .add_system_set(
+                SystemSet::on_update(GameState::Game)
+                    .with_system(record_ghost.after("victory"))
+                    .with_system(move_ghost.after("victory")),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Victory)
+                    .with_system(save_ghost.after("record_result"))
+                    .with_system(despawn_ghost),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver)
+                    .with_system(save_ghost.after("record_result"))
+                    .with_system(despawn_ghost),
+            );
+    }
+}
+
+/// Races a shared ghost on its own map and mode.
+fn apply_ghost_settings(
+    shared: Res<SharedGhost>,
+    cli: Res<Cli>,
+    mut game_seed: ResMut<GameSeed>,
+    mut game_mode: ResMut<GameMode>,
+) {
+    if cli.seed.is_none() {
+        *game_seed = shared.0.seed.clone();
+    }
+    if cli.game_mode.is_none() {
+        *game_mode = shared.0.mode;
+    }
+}
+
+fn start_race(
+    mut commands: Commands,
+    game_seed: Res<GameSeed>,
+    game_mode: Res<GameMode>,
+    shared: Option<Res<SharedGhost>>,
+    mut race: ResMut<RaceGhost>,
+    mut recording: ResMut<GhostRecording>,
+) {
+    recording.0.clear();
+    race.0 = match shared {
+        Some(shared) if shared.0.seed == *game_seed && shared.0.mode == *game_mode => {
+            Some(shared.0.clone())
+        }
+        _ => Ghost::from_storage(&game_seed, *game_mode),
+    };
+    let Some(start) = race.0.as_ref().and_then(|ghost| ghost.transform_at(0)) else {
+        return;
+    };
+    commands.spawn((
+        TransformBundle::from_transform(start),
+        GhostShip,
+        RigidBody::KinematicPositionBased,
+        Collider::triangle(UNIT_POINTS[0], UNIT_POINTS[1], UNIT_POINTS[2]),
+        // No membership, so nothing ever touches the ghost.
+        CollisionGroups::new(Group::from_bits(0).unwrap(), Group::from_bits(0).unwrap()),
+        Sensor,
+    ));
+}
+
+fn record_ghost(
+    clock: Res<RoundClock>,
+    ships: Query<(&Transform, &Pilot), With<Playable>>,
+    mut recording: ResMut<GhostRecording>,
+) {
+    let millis = clock.millis();
+    if let Some(last) = recording.0.last() {
+        if millis < last.millis + GHOST_SAMPLE_MILLIS {
+            return;
+        }
+    }
+    // Only the first pilot's run is kept.
+    let Some((trans, _)) = ships.iter().find(|(_, pilot)| pilot.0 == 0) else {
+        return;
+    };
+    recording.0.push(GhostSample {
+        millis,
+        pos: trans.translation.truncate(),
+        angle: trans.rotation.to_euler(EulerRot::XYZ).2,
+    });
+}
+
+fn move_ghost(
+    clock: Res<RoundClock>,
+    race: Res<RaceGhost>,
+    mut ghosts: Query<&mut Transform, With<GhostShip>>,
+) {
+    let Some(now) = race
+        .0
+        .as_ref()
+        .and_then(|ghost| ghost.transform_at(clock.millis()))
+    else {
+        return;
+    };
+    for mut trans in ghosts.iter_mut() {
+        *trans = now;
+    }
+}
+
+fn save_ghost(
+    cli: Res<Cli>,
+    tutorial: Res<Tutorial>,
+    last: Res<LastResult>,
+    game_seed: Res<GameSeed>,
+    game_mode: Res<GameMode>,
+    recording: Res<GhostRecording>,
+) {
+    if !counts(&cli, &tutorial) {
+        return;
+    }
+    let Some(result) = last.value else {
+        return;
+    };
+    if recording.0.is_empty() {
+        return;
+    }
+    if let Some(best) = Ghost::from_storage(&game_seed, *game_mode) {
+        if !best.beaten_by(result) {
+            return;
+        }
+    }
+    let ghost = Ghost {
+        seed: game_seed.clone(),
+        mode: *game_mode,
+        result,
+        samples: recording.0.clone(),
+    };
+    storage::save(
+        &Ghost::storage_key(&game_seed, *game_mode),
+        &ghost.to_text(),
+    );
+}
+
+fn despawn_ghost(mut commands: Commands, ghosts: Query<Entity, With<GhostShip>>) {
+    for ghost in ghosts.iter() {
+        commands.entity(ghost).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A time attack run finished in 1.2s, moving right along the x axis.
+    fn ghost() -> Ghost {
+        let sample = |millis, x| GhostSample {
+            millis,
+            pos: Vec2::new(x, 0.0),
+            angle: 0.0,
+        };
+        Ghost {
+            seed: GameSeed("abc".to_string()),
+            mode: GameMode::TimeAttack,
+            result: 1200,
+            samples: vec![sample(0, 0.0), sample(100, 10.0), sample(200, 30.0)],
+        }
+    }
+
+    #[test]
+    fn parse_reads_back_to_text() {
+        let ghost = ghost();
+        let parsed = Ghost::parse(&ghost.to_text()).unwrap();
+        assert_eq!(parsed.seed, ghost.seed);
+        assert_eq!(parsed.mode, ghost.mode);
+        assert_eq!(parsed.result, ghost.result);
+        assert_eq!(parsed.samples.len(), 3);
+        assert_eq!(parsed.samples[2].pos, Vec2::new(30.0, 0.0));
+        assert!(Ghost::parse("seed abc\nmode time-attack\n").is_err());
+        assert!(Ghost::parse("seed abc\nmode sprint\nresult 1\n").is_err());
+        assert!(Ghost::parse("seed abc\nmode time-attack\nresult 1\n0,1,2\n").is_err());
+        assert!(Ghost::parse("seed ../abc\nmode time-attack\nresult 1\n").is_err());
+    }
+
+    #[test]
+    fn transform_at_follows_the_samples() {
+        let ghost = ghost();
+        let x = |millis| ghost.transform_at(millis).unwrap().translation.x;
+        assert_eq!(x(0), 0.0);
+        assert_eq!(x(50), 5.0);
+        assert_eq!(x(150), 20.0);
+        assert_eq!(x(500), 30.0);
+        let empty = Ghost {
+            samples: Vec::new(),
+            ..ghost.clone()
+        };
+        assert!(empty.transform_at(0).is_none());
+    }
+
+    #[test]
+    fn only_a_better_run_replaces_the_ghost() {
+        let fastest = ghost();
+        assert!(fastest.beaten_by(1100));
+        assert!(!fastest.beaten_by(1200));
+        assert!(!fastest.beaten_by(1300));
+        let richest = Ghost {
+            mode: GameMode::ScoreAttack,
+            ..ghost()
+        };
+        assert!(richest.beaten_by(1300));
+        assert!(!richest.beaten_by(1200));
+        assert!(!richest.beaten_by(1100));
+    }
+}
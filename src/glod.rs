@@ -10,6 +10,7 @@ pub struct GlodPlugin;
 impl Plugin for GlodPlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(SystemSet::on_enter(GameState::Game).with_system(spawn_glod))
+            .add_system_set(SystemSet::on_update(GameState::Game).with_system(bounce_drifting_glod))
             .add_system_set(SystemSet::on_enter(GameState::Victory).with_system(despawn_glod))
             .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(despawn_glod));
     }
@@ -18,6 +19,64 @@ impl Plugin for GlodPlugin {
 pub const GLOD_RADIUS: f32 = 2.0;
 pub const GLOD_QUANTITY: usize = 50;
 pub const GLOD_MASS: f32 = 2.0;
+/// Chance that a seeded glod is rare, and that it is heavy.
+pub const RARE_GLOD_CHANCE: f64 = 0.08;
+pub const HEAVY_GLOD_CHANCE: f64 = 0.15;
+/// Drifting glod and asteroids turn back this far from the centre, before they leave the arena.
+pub const DRIFT_EDGE: f32 = 316.0;
+
+/// How much a glod scores and how much it weighs down the ship carrying it.
+#[derive(Component, Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum GlodTier {
+    #[default]
+    Common,
+    Rare,
+    Heavy,
+}
+
+impl GlodTier {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GlodTier::Common => "common",
+            GlodTier::Rare => "rare",
+            GlodTier::Heavy => "heavy",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GlodTier> {
+        [GlodTier::Common, GlodTier::Rare, GlodTier::Heavy]
+            .into_iter()
+            .find(|tier| tier.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn value(&self) -> u8 {
+        match self {
+            GlodTier::Common => 1,
+            GlodTier::Rare => 5,
+            GlodTier::Heavy => 3,
+        }
+    }
+
+    /// Weight in common glod, which the difficulty turns into mass.
+    pub fn weight(&self) -> u8 {
+        match self {
+            GlodTier::Common | GlodTier::Rare => 1,
+            GlodTier::Heavy => 4,
+        }
+    }
+
+    pub fn radius(&self) -> f32 {
+        match self {
+            GlodTier::Common => GLOD_RADIUS,
+            GlodTier::Rare => 1.5 * GLOD_RADIUS,
+            GlodTier::Heavy => 2.0 * GLOD_RADIUS,
+        }
+    }
+}
+
+/// Glod that keeps floating about instead of settling.
+#[derive(Component, Clone)]
+pub struct Drifting;
 
 fn despawn_glod(mut commands: Commands, glod: Query<Entity, With<IsGlod>>) {
     for ent in glod.iter() {
@@ -26,31 +85,62 @@ fn despawn_glod(mut commands: Commands, glod: Query<Entity, With<IsGlod>>) {
 }
 
 fn spawn_glod(mut commands: Commands, glods: Res<GlodPoints>) {
-    for glod in (*glods).glods.iter() {
-        commands
-            .spawn((
-            RigidBody::Dynamic,
-            Collider::ball(GLOD_RADIUS),
-            CollisionGroups::new(
-                Group::from_bits(CollisionMemberships::Glod as u32).unwrap(),
-                Group::from_bits(CollisionFilters::WithFriend as u32).unwrap(),
-            ),
-            Damping {
-                linear_damping: 2.0,
-                angular_damping: 0.0,
-            },
-            Sensor, 
-            IsGlod,
-            Explodee,
-            ExternalForce {
-                force: Vec2::ZERO,
-                torque: 0.0,
-            },
-            ExternalImpulse {
-                impulse: Vec2::ZERO,
-                torque_impulse: 0.0,
-            },
-            ColliderMassProperties::Mass(GLOD_MASS),
-            Transform::from_translation(*glod)));
+    for ((glod, drift), tier) in glods.glods.iter().zip(&glods.drift).zip(&glods.tiers) {
+        let entity = spawn_glod_body(&mut commands, *glod, *drift, *tier);
+        if *drift != Vec2::ZERO {
+            commands.entity(entity).insert((
+                Drifting,
+                Damping {
+                    linear_damping: 0.0,
+                    angular_damping: 0.0,
+                },
+            ));
+        }
     }
 }
+
+fn bounce_drifting_glod(mut glods: Query<(&Transform, &mut Velocity), With<Drifting>>) {
+    for (trans, mut velocity) in glods.iter_mut() {
+        let pos = trans.translation;
+        if pos.x.abs() > DRIFT_EDGE && pos.x * velocity.linvel.x > 0.0 {
+            velocity.linvel.x = -velocity.linvel.x;
+        }
+        if pos.y.abs() > DRIFT_EDGE && pos.y * velocity.linvel.y > 0.0 {
+            velocity.linvel.y = -velocity.linvel.y;
+        }
+    }
+}
+
+pub fn spawn_glod_body(commands: &mut Commands, pos: Vec3, linvel: Vec2, tier: GlodTier) -> Entity {
+    commands
+        .spawn((
+        RigidBody::Dynamic,
+        Collider::ball(tier.radius()),
+        CollisionGroups::new(
+            Group::from_bits(CollisionMemberships::Glod as u32).unwrap(),
+            Group::from_bits(CollisionFilters::WithFriend as u32).unwrap(),
+        ),
+        Damping {
+            linear_damping: 2.0,
+            angular_damping: 0.0,
+        },
+        Sensor, 
+        IsGlod,
+        tier,
+        Explodee,
+        ExternalForce {
+            force: Vec2::ZERO,
+            torque: 0.0,
+        },
+        ExternalImpulse {
+            impulse: Vec2::ZERO,
+            torque_impulse: 0.0,
+        },
+        Velocity {
+            linvel,
+            angvel: 0.0,
+        },
+        ColliderMassProperties::Mass(tier.weight() as f32 * GLOD_MASS),
+        Transform::from_translation(pos)))
+        .id()
+}
@@ -0,0 +1,124 @@
+use crate::{exit_ui_despawn, Ui};
+use crate::{AchievementUnlocked, Achievements, AppState, ACHIEVEMENTS};
+use bevy::prelude::*;
+
+pub struct UIAchievementsPlugin;
+
+impl Plugin for UIAchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(AppState::Achievements).with_system(achievements_menu),
+        )
+        .add_system_set(
+            SystemSet::on_update(AppState::Achievements).with_system(achievements_key_input),
+        )
+        .add_system_set(SystemSet::on_exit(AppState::Achievements).with_system(exit_ui_despawn))
+        .add_system(show_toasts)
+        .add_system(fade_toasts);
+    }
+}
+
+const TOAST_SECONDS: f32 = 3.0;
+
+/// Pop-up announcing a newly unlocked achievement.
+#[derive(Component)]
+struct Toast(Timer);
+
+fn achievements_text(achievements: &Achievements) -> String {
+    let lines: Vec<String> = ACHIEVEMENTS
+        .iter()
+        .map(|achievement| {
+            let mark = if achievements.is_unlocked(*achievement) {
+                "x"
+            } else {
+                " "
+            };
+            format!(
+                "[{}] {}: {}",
+                mark,
+                achievement.name(),
+                achievement.description()
+            )
+        })
+        .collect();
+    format!(
+        "Achievements\n\n{}\n\nEsc: return to main menu",
+        lines.join("\n")
+    )
+}
+
+fn achievements_menu(
+    mut commands: Commands,
+    achievements: Res<Achievements>,
+    asset_server: Res<AssetServer>,
+) {
+    let text_alignment = TextAlignment::CENTER;
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                achievements_text(&achievements),
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::WHITE,
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                },
+            )
+            .with_alignment(text_alignment),
+            ..default()
+        })
+        .insert(Ui);
+}
+
+fn achievements_key_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        app_state.set(AppState::MainMenu).unwrap();
+    }
+}
+
+/// Toasts stack upwards from the bottom right, whatever screen is showing.
+fn show_toasts(
+    mut commands: Commands,
+    mut unlocked: EventReader<AchievementUnlocked>,
+    toasts: Query<(), With<Toast>>,
+    asset_server: Res<AssetServer>,
+) {
+    for (i, AchievementUnlocked(achievement)) in unlocked.iter().enumerate() {
+        let row = (toasts.iter().count() + i) as f32;
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    format!("Achievement unlocked: {}", achievement.name()),
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::GOLD,
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    },
+                )
+                .with_alignment(TextAlignment::BOTTOM_RIGHT),
+                transform: Transform::from_xyz(310.0, -310.0 + 24.0 * row, 2.0),
+                ..default()
+            },
+            Toast(Timer::from_seconds(TOAST_SECONDS, TimerMode::Once)),
+        ));
+    }
+}
+
+fn fade_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toasts: Query<(Entity, &mut Toast, &mut Text)>,
+) {
+    for (entity, mut toast, mut text) in toasts.iter_mut() {
+        if toast.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let alpha = (toast.0.remaining_secs() / TOAST_SECONDS * 3.0).min(1.0);
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
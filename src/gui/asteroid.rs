@@ -0,0 +1,37 @@
+use crate::{Asteroid, Drawn, GameState};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude as lyon;
+
+pub struct UIAsteroidPlugin;
+
+impl Plugin for UIAsteroidPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(GameState::Game).with_system(draw_asteroids));
+    }
+}
+
+fn draw_asteroids(
+    mut commands: Commands,
+    asteroids: Query<(Entity, &Transform, &Asteroid), Without<Drawn>>,
+) {
+    for (entity, trans, asteroid) in asteroids.iter() {
+        // Asteroids that break up are drawn lighter, so they can be told apart.
+        let colour = if asteroid.splits {
+            Color::SILVER
+        } else {
+            Color::GRAY
+        };
+        commands
+            .entity(entity)
+            .insert(Drawn)
+            .insert(lyon::GeometryBuilder::build_as(
+                &lyon::shapes::RegularPolygon {
+                    sides: 7,
+                    feature: lyon::shapes::RegularPolygonFeature::Radius(asteroid.size.radius()),
+                    center: Vec2::ZERO,
+                },
+                lyon::DrawMode::Stroke(lyon::StrokeMode::new(colour, 2.0)),
+                *trans,
+            ));
+    }
+}
@@ -0,0 +1,213 @@
+use crate::gui::preview::{spawn_map_preview, MapPreview};
+use crate::storage;
+use crate::{exit_ui_despawn, Ui};
+use crate::{AppState, GameSeed, MapConstraints, MapLayout, PlayerMode};
+use bevy::prelude::*;
+
+pub struct UIBrowserPlugin;
+
+impl Plugin for UIBrowserPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Favourites::from_storage(
+            &storage::load("favourite_seeds").unwrap_or_default(),
+        ))
+        .insert_resource(SeedBrowser::default())
+        .add_system_set(SystemSet::on_enter(AppState::SeedBrowser).with_system(browser_menu))
+        .add_system_set(
+            SystemSet::on_update(AppState::SeedBrowser)
+                .with_system(browser_key_input.label("input"))
+                .with_system(redraw_browser.after("input")),
+        )
+        .add_system_set(SystemSet::on_exit(AppState::SeedBrowser).with_system(exit_ui_despawn))
+        .add_system(save_favourites);
+    }
+}
+
+/// Seeds the player has starred in the seed browser.
+#[derive(Resource, Default)]
+pub struct Favourites(pub Vec<GameSeed>);
+
+impl Favourites {
+    fn from_storage(stored: &str) -> Favourites {
+        Favourites(
+            stored
+                .split(',')
+                .filter(|seed| !seed.is_empty())
+                .map(|seed| GameSeed(seed.to_string()))
+                .collect(),
+        )
+    }
+
+    fn to_storage(&self) -> String {
+        self.0
+            .iter()
+            .map(|seed| seed.0.clone())
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    pub fn contains(&self, seed: &GameSeed) -> bool {
+        self.0.contains(seed)
+    }
+
+    pub fn toggle(&mut self, seed: &GameSeed) {
+        match self.0.iter().position(|s| s == seed) {
+            Some(i) => {
+                self.0.remove(i);
+            }
+            None => self.0.push(seed.clone()),
+        }
+    }
+}
+
+/// Seeds seen so far in the browser, so that going back shows the same maps.
+#[derive(Resource, Default)]
+struct SeedBrowser {
+    seen: Vec<GameSeed>,
+    current: usize,
+}
+
+impl SeedBrowser {
+    fn seed(&self) -> &GameSeed {
+        &self.seen[self.current]
+    }
+
+    fn next(&mut self) {
+        self.current += 1;
+        if self.current == self.seen.len() {
+            self.seen.push(GameSeed::default());
+        }
+    }
+
+    fn previous(&mut self) {
+        self.current = self.current.saturating_sub(1);
+    }
+
+    fn show(&mut self, seed: &GameSeed) {
+        self.seen.truncate(self.current + 1);
+        self.seen.push(seed.clone());
+        self.current = self.seen.len() - 1;
+    }
+}
+
+/// Parts of the browser that follow the seed on show.
+#[derive(Component)]
+struct BrowsedSeed;
+
+fn browser_menu(
+    mut commands: Commands,
+    game_seed: Res<GameSeed>,
+    mut browser: ResMut<SeedBrowser>,
+    asset_server: Res<AssetServer>,
+) {
+    if browser.seen.is_empty() {
+        browser.seen.push(game_seed.clone());
+    } else {
+        // Mark as changed so the current seed is drawn again.
+        browser.set_changed();
+    }
+
+    let text_alignment = TextAlignment::CENTER;
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                "Seed Browser\n\nLeft/Right: previous/next seed\nUp/Down: cycle favourites\nF: favourite\nEnter: play\nEsc: return to main menu",
+                TextStyle {
+                    font_size: 28.0,
+                    color: Color::WHITE,
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                },
+            )
+            .with_alignment(text_alignment),
+            transform: Transform::from_xyz(0.0, 220.0, 0.0),
+            ..default()
+        })
+        .insert(Ui);
+}
+
+fn redraw_browser(
+    mut commands: Commands,
+    browser: Res<SeedBrowser>,
+    favourites: Res<Favourites>,
+    constraints: Res<MapConstraints>,
+    mode: Res<PlayerMode>,
+    shown: Query<Entity, Or<(With<BrowsedSeed>, With<MapPreview>)>>,
+    asset_server: Res<AssetServer>,
+) {
+    if !browser.is_changed() && !favourites.is_changed() {
+        return;
+    }
+    for entity in shown.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let seed = browser.seed();
+    let layout = MapLayout::generate(seed, &constraints, None, *mode);
+    let starred = if favourites.contains(seed) {
+        " (favourite)"
+    } else {
+        ""
+    };
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                format!("Seed: {} ({}){}", seed.0, layout.pattern.name(), starred),
+                TextStyle {
+                    font_size: 36.0,
+                    color: Color::WHITE,
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                },
+            )
+            .with_alignment(TextAlignment::CENTER),
+            transform: Transform::from_xyz(0.0, 90.0, 0.0),
+            ..default()
+        })
+        .insert((BrowsedSeed, Ui));
+
+    let preview = spawn_map_preview(&mut commands, &layout, Vec3::new(0.0, -150.0, 0.0), 0.35);
+    commands.entity(preview).insert(Ui);
+}
+
+fn browser_key_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut app_state: ResMut<State<AppState>>,
+    mut game_seed: ResMut<GameSeed>,
+    mut browser: ResMut<SeedBrowser>,
+    mut favourites: ResMut<Favourites>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Right) {
+        browser.next();
+    } else if keyboard_input.just_pressed(KeyCode::Left) {
+        browser.previous();
+    } else if keyboard_input.any_just_pressed([KeyCode::Up, KeyCode::Down]) {
+        if favourites.0.is_empty() {
+            return;
+        }
+        let count = favourites.0.len();
+        let step = if keyboard_input.just_pressed(KeyCode::Down) {
+            1
+        } else {
+            count - 1
+        };
+        let next = match favourites.0.iter().position(|s| s == browser.seed()) {
+            Some(i) => (i + step) % count,
+            None => 0,
+        };
+        let seed = favourites.0[next].clone();
+        browser.show(&seed);
+    } else if keyboard_input.just_pressed(KeyCode::F) {
+        let seed = browser.seed().clone();
+        favourites.toggle(&seed);
+    } else if keyboard_input.just_pressed(KeyCode::Return) {
+        *game_seed = browser.seed().clone();
+        app_state.set(AppState::InGame).unwrap();
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        app_state.set(AppState::MainMenu).unwrap();
+    };
+}
+
+fn save_favourites(favourites: Res<Favourites>) {
+    if favourites.is_changed() && !favourites.is_added() {
+        storage::save("favourite_seeds", &favourites.to_storage());
+    }
+}
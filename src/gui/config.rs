@@ -1,4 +1,9 @@
+use crate::Cli;
+use bevy::app::ScheduleRunnerPlugin;
 use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
+use bevy::render::settings::WgpuSettings;
+use bevy::winit::WinitPlugin;
 use bevy_prototype_lyon::prelude::ShapePlugin;
 extern crate web_sys;
 
@@ -15,23 +20,49 @@ pub struct UIConfigPlugin;
 
 impl Plugin for UIConfigPlugin {
     fn build(&self, app: &mut App) {
+        let cli = app.world.resource::<Cli>().clone();
         app.insert_resource(Msaa::default())
         .insert_resource(ClearColor(Color::rgb(0.0, 0.0, 0.0)))
-        .add_startup_system(setup.label("main_setup"))
-        //        .add_plugin(RapierDebugRenderPlugin::default())
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
+        .add_startup_system(setup.label("main_setup"));
+        if cli.headless {
+            // No window or GPU; the game still runs, just nothing is drawn.
+            app.insert_resource(WgpuSettings {
+                backends: None,
+                ..default()
+            })
+            .add_plugins(
+                DefaultPlugins
+                    .set(WindowPlugin {
+                        add_primary_window: false,
+                        exit_on_all_closed: false,
+                        ..default()
+                    })
+                    .disable::<WinitPlugin>(),
+            )
+            .add_plugin(ScheduleRunnerPlugin);
+            return;
+        }
+        let (width, height) = cli.window_size.unwrap_or((640.0, 640.0));
+        app.add_plugins(DefaultPlugins.set(WindowPlugin {
             window: WindowDescriptor {
                 title: "GlodGET".to_string(),
-                width: 640.0,
-                height: 640.0,
+                width,
+                height,
                 ..default()
             },
         ..default()
         }))
+        //        .add_plugin(RapierDebugRenderPlugin::default())
         .add_plugin(ShapePlugin);
     }
 }
 
 fn setup(mut commands: Commands) {
-    commands.spawn(Camera2dBundle::default());
+    // Keep the whole arena in view whatever the window size.
+    let mut camera = Camera2dBundle::default();
+    camera.projection.scaling_mode = ScalingMode::Auto {
+        min_width: 640.0,
+        min_height: 640.0,
+    };
+    commands.spawn(camera);
 }
@@ -0,0 +1,36 @@
+use crate::{Flare, FogRule, GameState, IsBase, IsEnemy, IsGlod, Playable, Sonar};
+use bevy::prelude::*;
+
+pub struct UIFogPlugin;
+
+impl Plugin for UIFogPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(GameState::Game).with_system(apply_fog));
+    }
+}
+
+/// Hides glod and enemies that no ship, base, flare or ping can see.
+fn apply_fog(
+    rule: Res<FogRule>,
+    sonar: Res<Sonar>,
+    sensors: Query<&GlobalTransform, Or<(With<Playable>, With<IsBase>)>>,
+    flares: Query<(&GlobalTransform, &Flare)>,
+    mut hidden: Query<(&GlobalTransform, &mut Visibility), Or<(With<IsGlod>, With<IsEnemy>)>>,
+) {
+    if !rule.enabled {
+        return;
+    }
+    for (trans, mut visibility) in hidden.iter_mut() {
+        let pos = trans.translation();
+        let seen = sonar.revealing()
+            || sensors
+                .iter()
+                .any(|sensor| sensor.translation().distance(pos) <= rule.sensor_radius)
+            || flares
+                .iter()
+                .any(|(flare, lit)| flare.translation().distance(pos) <= lit.radius);
+        if visibility.is_visible != seen {
+            visibility.is_visible = seen;
+        }
+    }
+}
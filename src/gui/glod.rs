@@ -1,5 +1,4 @@
-use crate::GLOD_RADIUS;
-use crate::{Drawn, GameState, IsGlod};
+use crate::{Drawn, GameState, GlodTier, IsGlod, Pickup, PICKUP_RADIUS};
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude as lyon;
 
@@ -11,20 +10,52 @@ impl Plugin for UIGlodPlugin {
     }
 }
 
+pub fn tier_colour(tier: GlodTier) -> Color {
+    match tier {
+        GlodTier::Common => Color::YELLOW,
+        GlodTier::Rare => Color::FUCHSIA,
+        GlodTier::Heavy => Color::ORANGE,
+    }
+}
+
+pub fn pickup_colour(pickup: Pickup) -> Color {
+    match pickup {
+        Pickup::SpeedBoost => Color::LIME_GREEN,
+        Pickup::Shield => Color::CYAN,
+        Pickup::TorpedoRefill => Color::WHITE,
+    }
+}
+
 fn draw_glod(
     mut commands: Commands,
-    glods: Query<(Entity, &Transform), (With<IsGlod>, Without<Drawn>)>,
+    glods: Query<(Entity, &Transform, &GlodTier), (With<IsGlod>, Without<Drawn>)>,
+    pickups: Query<(Entity, &Transform, &Pickup), Without<Drawn>>,
 ) {
-    for (glod, trans) in glods.iter() {
+    for (glod, trans, tier) in glods.iter() {
         commands
             .entity(glod)
             .insert(Drawn)
             .insert(lyon::GeometryBuilder::build_as(
                 &lyon::shapes::Circle {
-                    radius: GLOD_RADIUS,
+                    radius: tier.radius(),
+                    center: Vec2::ZERO,
+                },
+                lyon::DrawMode::Fill(lyon::FillMode::color(tier_colour(*tier))),
+                *trans,
+            ));
+    }
+
+    // Pickups are rings, so they can't be mistaken for glod.
+    for (entity, trans, pickup) in pickups.iter() {
+        commands
+            .entity(entity)
+            .insert(Drawn)
+            .insert(lyon::GeometryBuilder::build_as(
+                &lyon::shapes::Circle {
+                    radius: PICKUP_RADIUS,
                     center: Vec2::ZERO,
                 },
-                lyon::DrawMode::Fill(lyon::FillMode::color(Color::YELLOW)),
+                lyon::DrawMode::Stroke(lyon::StrokeMode::new(pickup_colour(*pickup), 2.0)),
                 *trans,
             ));
     }
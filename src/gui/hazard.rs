@@ -0,0 +1,58 @@
+use crate::{Drawn, GameState, Hazard, HazardKind};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude as lyon;
+
+pub struct UIHazardPlugin;
+
+impl Plugin for UIHazardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(GameState::Game).with_system(draw_hazards));
+    }
+}
+
+/// Hazards sit behind everything else.
+const HAZARD_DEPTH: f32 = -1.0;
+
+fn draw_hazards(
+    mut commands: Commands,
+    hazards: Query<(Entity, &Transform, &Hazard), Without<Drawn>>,
+) {
+    for (entity, trans, hazard) in hazards.iter() {
+        let circle = lyon::shapes::Circle {
+            radius: hazard.radius,
+            center: Vec2::ZERO,
+        };
+        let mut builder = lyon::GeometryBuilder::new().add(&circle);
+        let draw_mode = match hazard.kind {
+            HazardKind::Well => lyon::DrawMode::Stroke(lyon::StrokeMode::new(Color::PURPLE, 2.0)),
+            HazardKind::Repulsor => {
+                lyon::DrawMode::Stroke(lyon::StrokeMode::new(Color::ORANGE, 2.0))
+            }
+            HazardKind::Current(direction) => {
+                // An arrow across the middle shows which way it flows.
+                let tip = 0.6 * hazard.radius * direction;
+                let barb = 0.2 * hazard.radius;
+                builder = builder
+                    .add(&lyon::shapes::Line(-tip, tip))
+                    .add(&lyon::shapes::Line(
+                        tip,
+                        tip - barb * Vec2::from_angle(0.5).rotate(direction),
+                    ))
+                    .add(&lyon::shapes::Line(
+                        tip,
+                        tip - barb * Vec2::from_angle(-0.5).rotate(direction),
+                    ));
+                lyon::DrawMode::Stroke(lyon::StrokeMode::new(Color::CYAN, 2.0))
+            }
+            HazardKind::Nebula => {
+                lyon::DrawMode::Fill(lyon::FillMode::color(Color::rgba(0.5, 0.4, 0.8, 0.3)))
+            }
+        };
+        let mut trans = *trans;
+        trans.translation.z = HAZARD_DEPTH;
+        commands
+            .entity(entity)
+            .insert(Drawn)
+            .insert(builder.build(draw_mode, trans));
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{
+    BaseHealth, Cargo, DepositRule, EnemyBase, Difficulty, FogRule, GameMode, GameSeed, GameState, GlodPoints, Pilot, PlayerMode,
+    Playable, RoundClock, Score, Shielded, Sonar, SpeedBoost, Team, Wave, WeaponPreLaunch,
+    SCORE_ATTACK_SECONDS,
+};
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct Hud;
+
+pub struct UIHudPlugin;
+
+impl Plugin for UIHudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Game).with_system(spawn_hud))
+            .add_system_set(SystemSet::on_update(GameState::Game).with_system(update_hud))
+            .add_system_set(SystemSet::on_exit(GameState::Game).with_system(despawn_hud));
+    }
+}
+
+fn spawn_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                },
+            )
+            .with_alignment(TextAlignment::TOP_LEFT),
+            transform: Transform::from_xyz(-312.0, 312.0, 1.0),
+            ..default()
+        },
+        Hud,
+    ));
+}
+
+fn despawn_hud(mut commands: Commands, hud: Query<Entity, With<Hud>>) {
+    for entity in hud.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn update_hud(
+    score: Res<Score>,
+    game_seed: Res<GameSeed>,
+    glod_points: Res<GlodPoints>,
+    difficulty: Res<Difficulty>,
+    deposit: Res<DepositRule>,
+    mode: Res<PlayerMode>,
+    game_mode: Res<GameMode>,
+    clock: Res<RoundClock>,
+    wave: Res<Wave>,
+    fog: Res<FogRule>,
+    sonar: Res<Sonar>,
+    enemy_base: Query<&BaseHealth, With<EnemyBase>>,
+    ships: Query<(Entity, &Pilot, &Cargo, &Team), With<Playable>>,
+    effects: Query<(Option<&SpeedBoost>, Option<&Shielded>)>,
+    weapons: Query<&Parent, With<WeaponPreLaunch>>,
+    mut hud: Query<&mut Text, With<Hud>>,
+) {
+    let cap = match difficulty.glod_mass_cap() {
+        Some(cap) => format!("{:.0}", cap),
+        None => "-".to_string(),
+    };
+    let mut value = format!("Seed: {}\n", game_seed.0);
+    match *game_mode {
+        GameMode::Classic => (),
+        GameMode::TimeAttack => value += &format!("Time: {:.1}s\n", clock.seconds()),
+        GameMode::ScoreAttack => {
+            let left = f32::max(SCORE_ATTACK_SECONDS - clock.seconds(), 0.0);
+            value += &format!("Time left: {:.0}s\n", left.ceil());
+        }
+        GameMode::Survival => {
+            value += &format!("Survived: {:.0}s Wave: {}\n", clock.seconds(), wave.0)
+        }
+    }
+    for health in enemy_base.iter() {
+        value += &format!("Enemy base: {}/{}\n", health.current, health.max);
+    }
+    if fog.enabled {
+        match sonar.recharge_left() {
+            left if left > 0.0 => value += &format!("Sonar: {:.0}s\n", left.ceil()),
+            _ => value += "Sonar: ready\n",
+        }
+    }
+    for team in 0..mode.teams() {
+        if mode.teams() > 1 {
+            value += &format!("P{} ", team + 1);
+        }
+        if deposit.enabled {
+            let carried = ships
+                .iter()
+                .filter(|(_, _, _, t)| t.0 == team)
+                .map(|(_, _, cargo, _)| cargo.value())
+                .sum::<u32>();
+            value += &format!(
+                "Banked: {}/{} (carrying {})\n",
+                score.0[team], deposit.victory_minimum, carried
+            );
+        } else {
+            value += &format!("Glod: {}/{}\n", score.0[team], glod_points.total_value());
+        }
+    }
+
+    let mut ships: Vec<(Entity, &Pilot, &Cargo, &Team)> = ships.iter().collect();
+    ships.sort_by_key(|(_, pilot, _, _)| pilot.0);
+    for (ship, pilot, cargo, _) in ships {
+        let stock = weapons.iter().filter(|parent| parent.get() == ship).count();
+        if mode.players() > 1 {
+            value += &format!("P{} ", pilot.0 + 1);
+        }
+        value += &format!(
+            "Load: {:.0}/{} Torpedoes: {}",
+            difficulty.cargo_mass(cargo.weight()),
+            cap,
+            stock
+        );
+        if let Ok((boost, shield)) = effects.get(ship) {
+            if boost.is_some() {
+                value += " Boost";
+            }
+            if shield.is_some() {
+                value += " Shield";
+            }
+        }
+        value += "\n";
+    }
+    for mut text in hud.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
@@ -1,6 +1,8 @@
-use crate::log;
-use crate::GameSeed;
-use crate::{AppState, GameState, Score, HighScore};
+use crate::gui::preview::{spawn_map_preview, MapPreview};
+use crate::gui::text_input::TextInput;
+use crate::{copy_seed, paste_text, GameSeed, MapConstraints, MapLayout, SEED_MAX_LEN};
+use crate::{AppState, GameMode, GameState, LastResult, Leaderboards, PlayerMode, Score, Winner};
+use crate::{Rules, Tutorial};
 use bevy::prelude::*;
 
 #[derive(Component)]
@@ -10,28 +12,25 @@ pub struct UIMenuPlugin;
 
 impl Plugin for UIMenuPlugin {
     fn build(&self, app: &mut App) {
-        let local_storage = web_sys::window().unwrap().local_storage().unwrap().unwrap();
-        let high_score: u8 = local_storage.get_item("high_score").unwrap().unwrap_or("0".to_string()).parse::<u8>().unwrap();
         app.add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(main_menu))
-            .insert_resource(HighScore(high_score))
             .add_system_set(
                 SystemSet::on_update(AppState::MainMenu).with_system(main_menu_key_input),
             )
             .add_system_set(SystemSet::on_exit(AppState::MainMenu).with_system(exit_ui_despawn))
             .add_system_set(SystemSet::on_enter(AppState::SeedMenu).with_system(seed_menu))
+            .insert_resource(SeedInput(TextInput::new("", SEED_MAX_LEN, GameSeed::allows)))
             .add_system_set(
                 SystemSet::on_update(AppState::SeedMenu)
-                    .with_system(exit_ui_despawn.before("draw"))
                     .with_system(seed_menu_key_input.label("input"))
-                    .with_system(seed_menu.after("input").label("draw")),
+                    .with_system(redraw_seed_menu.after("input")),
             )
             .add_system_set(SystemSet::on_exit(AppState::SeedMenu).with_system(exit_ui_despawn))
-            .add_system_set(SystemSet::on_enter(GameState::Victory).with_system(victory.after("score_update")))
+            .add_system_set(SystemSet::on_enter(GameState::Victory).with_system(victory.after("record_result")))
             .add_system_set(
                 SystemSet::on_update(GameState::Victory).with_system(end_game_key_input),
             )
             .add_system_set(SystemSet::on_exit(GameState::Victory).with_system(exit_ui_despawn))
-            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(gameover))
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(gameover.after("record_result")))
             .add_system_set(
                 SystemSet::on_update(GameState::GameOver).with_system(end_game_key_input),
             )
@@ -39,25 +38,46 @@ impl Plugin for UIMenuPlugin {
     }
 }
 
-fn exit_ui_despawn(mut commands: Commands, query: Query<Entity, With<Ui>>) {
+pub fn exit_ui_despawn(mut commands: Commands, query: Query<Entity, With<Ui>>) {
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
     }
 }
 
-#[cfg(target_family = "wasm")]
-fn victory(mut commands: Commands, score: ResMut<Score>, high_score: ResMut<HighScore>, asset_server: Res<AssetServer>) {
+/// Best result for the mode, and whether the last game set it.
+fn best_text(mode: GameMode, boards: &Leaderboards, last: &LastResult) -> String {
+    let best = match boards.best(mode) {
+        Some(entry) => mode.format_result(entry.value),
+        None => "-".to_string(),
+    };
+    let record = if last.rank == Some(0) { " (new record!)" } else { "" };
+    format!("{} best: {}{}", mode.name(), best, record)
+}
+
+fn victory(mut commands: Commands, score: Res<Score>, boards: Res<Leaderboards>, last: Res<LastResult>, winner: Res<Winner>, mode: Res<PlayerMode>, game_mode: Res<GameMode>, game_seed: Res<GameSeed>, asset_server: Res<AssetServer>) {
     let text_alignment = TextAlignment::CENTER;
-    let local_storage = web_sys::window().unwrap().local_storage().unwrap().unwrap();
-    local_storage.set_item("high_score", &((*high_score).0.to_string())).unwrap();
+    let result = match (*mode, winner.0) {
+        (PlayerMode::Versus, Some(team)) => format!(
+            "Player {} wins!\nScores: {} - {}",
+            team + 1,
+            score.0[0],
+            score.0[1]
+        ),
+        _ => match *game_mode {
+            GameMode::Classic | GameMode::ScoreAttack => "You win!\nYour score: ".to_owned() + &score.0[0].to_string(),
+            GameMode::TimeAttack => "You win!\nYour time: ".to_owned() + &game_mode.format_result(last.value.unwrap_or(0)),
+            GameMode::Survival => "You win!\nSurvived: ".to_owned() + &game_mode.format_result(last.value.unwrap_or(0)),
+        },
+    };
     commands
         .spawn(Text2dBundle {
             text: Text::from_section(
-                "You win!\nYour score: ".to_owned()
-                    + &((*score).0.to_string()
-                        + "\n High score: "
-                        + &(*high_score).0.to_string()
-                        + "\nEnter: replay same map\ns: new map\nEsc: return to main menu"),
+                result
+                    + "\n"
+                    + &best_text(*game_mode, &boards, &last)
+                    + "\nSeed: "
+                    + &game_seed.0
+                    + "\nEnter: replay same map\ns: new map\nc: copy seed\nu: upgrades\nEsc: return to main menu",
                 TextStyle {
         font_size: 60.0,
         color: Color::WHITE,
@@ -70,15 +90,14 @@ fn victory(mut commands: Commands, score: ResMut<Score>, high_score: ResMut<High
         .insert(Ui);
 }
 
-#[cfg(target_family = "wasm")]
-fn main_menu(mut commands: Commands, high_score: ResMut<HighScore>, asset_server: Res<AssetServer>) {
+fn main_menu(mut commands: Commands, boards: Res<Leaderboards>, game_mode: Res<GameMode>, asset_server: Res<AssetServer>) {
     let text_alignment = TextAlignment::CENTER;
     commands
         .spawn(Text2dBundle {
             text: Text::from_section(
-                "GlodGET\n\nHigh Score: ".to_owned() + &((*high_score).0.to_string() + "\n\nEnter: New Game\nS: set game seed"),
+                "GlodGET\n\n".to_owned() + &(best_text(*game_mode, &boards, &LastResult::default()) + "\n\nEnter: New Game\nD: daily challenge\nS: set game seed\nB: browse seeds\nU: upgrades\nO: options\nT: tutorial\nA: achievements"),
                 TextStyle {
-        font_size: 60.0,
+        font_size: 40.0,
         color: Color::WHITE,
         font: asset_server.load("fonts/FiraSans-Bold.ttf"),
     },
@@ -89,84 +108,174 @@ fn main_menu(mut commands: Commands, high_score: ResMut<HighScore>, asset_server
         .insert(Ui);
 }
 
-#[cfg(target_family = "wasm")]
 fn main_menu_key_input(
     keyboard_input: Res<Input<KeyCode>>,
     mut app_state: ResMut<State<AppState>>,
+    mut game_seed: ResMut<GameSeed>,
+    mut tutorial: ResMut<Tutorial>,
+    mut game_mode: ResMut<GameMode>,
+    mut player_mode: ResMut<PlayerMode>,
+    mut rules: Rules,
 ) {
     if keyboard_input.pressed(KeyCode::Return) {
         app_state.set(AppState::InGame).unwrap();
+    } else if keyboard_input.pressed(KeyCode::D) {
+        *game_seed = GameSeed::daily();
+        app_state.set(AppState::InGame).unwrap();
     } else if keyboard_input.pressed(KeyCode::S) {
         app_state.set(AppState::SeedMenu).unwrap();
+    } else if keyboard_input.pressed(KeyCode::B) {
+        app_state.set(AppState::SeedBrowser).unwrap();
+    } else if keyboard_input.pressed(KeyCode::U) {
+        app_state.set(AppState::Shop).unwrap();
+    } else if keyboard_input.pressed(KeyCode::O) {
+        app_state.set(AppState::Options).unwrap();
+    } else if keyboard_input.pressed(KeyCode::A) {
+        app_state.set(AppState::Achievements).unwrap();
+    } else if keyboard_input.pressed(KeyCode::T) {
+        tutorial.start(&mut game_seed, &mut game_mode, &mut player_mode, &mut rules);
+        app_state.set(AppState::InGame).unwrap();
     };
 }
 
-#[cfg(target_family = "wasm")]
-fn seed_menu(mut commands: Commands, game_seed: ResMut<GameSeed>, asset_server: Res<AssetServer>) {
+/// Seed being typed in the seed menu, applied when the game is launched.
+#[derive(Resource)]
+struct SeedInput(TextInput);
+
+/// Parts of the seed menu that follow the seed being typed.
+#[derive(Component)]
+struct SeedText;
+
+fn seed_menu(
+    mut commands: Commands,
+    game_seed: Res<GameSeed>,
+    mut input: ResMut<SeedInput>,
+    mut chars: ResMut<Events<ReceivedCharacter>>,
+    asset_server: Res<AssetServer>,
+) {
+    // Drop the key press that opened this menu.
+    chars.clear();
+    *input = SeedInput(TextInput::new(&game_seed.0, SEED_MAX_LEN, GameSeed::allows));
+
     let text_alignment = TextAlignment::CENTER;
     commands
         .spawn(Text2dBundle {
             text: Text::from_section(
-                "Seed Menu\n\nHit enter to launch game\nSeed:\n ".to_owned()
-                    + &((*game_seed).0.to_string()),
+                "Seed Menu\n\nEnter: launch game\nCtrl+V: paste\nEsc: return to main menu",
                 TextStyle {
-        font_size: 60.0,
+        font_size: 40.0,
         color: Color::WHITE,
         font: asset_server.load("fonts/FiraSans-Bold.ttf"),
     },
             )
             .with_alignment(text_alignment),
+            transform: Transform::from_xyz(0.0, 220.0, 0.0),
             ..default()
         })
         .insert(Ui);
 }
 
-#[cfg(target_family = "wasm")]
+fn redraw_seed_menu(
+    mut commands: Commands,
+    input: Res<SeedInput>,
+    constraints: Res<MapConstraints>,
+    mode: Res<PlayerMode>,
+    shown: Query<Entity, Or<(With<SeedText>, With<MapPreview>)>>,
+    asset_server: Res<AssetServer>,
+) {
+    if !input.is_changed() {
+        return;
+    }
+    for entity in shown.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                "Seed: ".to_owned() + &input.0.display(),
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                },
+            )
+            .with_alignment(TextAlignment::CENTER),
+            transform: Transform::from_xyz(0.0, 90.0, 0.0),
+            ..default()
+        })
+        .insert((SeedText, Ui));
+
+    let seed = GameSeed(input.0.value.clone());
+    let layout = MapLayout::generate(&seed, &constraints, None, *mode);
+    let preview = spawn_map_preview(&mut commands, &layout, Vec3::new(0.0, -150.0, 0.0), 0.35);
+    commands.entity(preview).insert(Ui);
+}
+
 fn seed_menu_key_input(
-    mut char_evr: EventReader<ReceivedCharacter>,
+    mut chars: EventReader<ReceivedCharacter>,
     keyboard_input: Res<Input<KeyCode>>,
     mut app_state: ResMut<State<AppState>>,
     mut game_seed: ResMut<GameSeed>,
+    mut input: ResMut<SeedInput>,
 ) {
-    for ev in char_evr.iter() {
-        let GameSeed(mut string) = (*game_seed).clone();
-        string.push(ev.char);
-        *game_seed = GameSeed(string);
+    // Only flag the input as changed when editing actually changed it.
+    if input
+        .bypass_change_detection()
+        .0
+        .update(&mut chars, &keyboard_input, paste_text)
+    {
+        input.set_changed();
     }
 
-    if keyboard_input.just_pressed(KeyCode::Back) {
-        let GameSeed(mut string) = (*game_seed).clone();
-        string.pop();
-        *game_seed = GameSeed(string);
-    };
-
-    if keyboard_input.pressed(KeyCode::Return) {
+    if keyboard_input.just_pressed(KeyCode::Return) && !input.0.value.is_empty() {
+        *game_seed = GameSeed(input.0.value.clone());
         app_state.set(AppState::InGame).unwrap();
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        app_state.set(AppState::MainMenu).unwrap();
     };
 }
 
-#[cfg(target_family = "wasm")]
 fn end_game_key_input(
     keyboard_input: Res<Input<KeyCode>>,
+    game_seed: Res<GameSeed>,
     mut app_state: ResMut<State<AppState>>,
     mut game_state: ResMut<State<GameState>>,
+    mut text: Query<&mut Text, With<Ui>>,
 ) {
-    if keyboard_input.pressed(KeyCode::Return) {
+    if keyboard_input.just_pressed(KeyCode::C) {
+        let note = "\n".to_owned() + copy_seed(&game_seed.0);
+        for mut text in text.iter_mut() {
+            if !text.sections[0].value.ends_with(&note) {
+                text.sections[0].value += &note;
+            }
+        }
+    } else if keyboard_input.pressed(KeyCode::Return) {
         game_state.set(GameState::Game).unwrap();
     } else if keyboard_input.pressed(KeyCode::Escape) {
         app_state.set(AppState::MainMenu).unwrap();
         game_state.set(GameState::OutOfGame).unwrap();
     } else if keyboard_input.pressed(KeyCode::S) {
         game_state.set(GameState::OutOfGame).unwrap();
+    } else if keyboard_input.pressed(KeyCode::U) {
+        app_state.set(AppState::Shop).unwrap();
+        game_state.set(GameState::OutOfGame).unwrap();
     };
 }
 
-fn gameover(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn gameover(mut commands: Commands, boards: Res<Leaderboards>, last: Res<LastResult>, game_mode: Res<GameMode>, game_seed: Res<GameSeed>, asset_server: Res<AssetServer>) {
     let text_alignment = TextAlignment::CENTER;
+    let result = match last.value {
+        Some(value) => format!("Game over!\nSurvived: {}\n{}", game_mode.format_result(value), best_text(*game_mode, &boards, &last)),
+        None => "You lose!".to_string(),
+    };
     commands
         .spawn(Text2dBundle {
             text: Text::from_section(
-                "You lose!\nEnter: replay same map\ns: new map\nEsc: return to main menu",
+                result
+                    + "\nSeed: "
+                    + &game_seed.0
+                    + "\nEnter: replay same map\ns: new map\nc: copy seed\nu: upgrades\nEsc: return to main menu",
                 TextStyle {
         font_size: 60.0,
         color: Color::WHITE,
@@ -1,6 +1,17 @@
+pub mod achievements;
+pub mod asteroid;
+pub mod browser;
 pub mod config;
+pub mod fog;
 pub mod glod;
+pub mod hazard;
+pub mod hud;
 pub mod menu;
+pub mod options;
+pub mod preview;
+pub mod shop;
 pub mod start;
+pub mod text_input;
+pub mod tutorial;
 pub mod unit;
 pub mod weapon;
@@ -0,0 +1,146 @@
+use crate::{exit_ui_despawn, Ui};
+use crate::{AppState, BaseConfig, Difficulty, GameMode, PlayerMode, Rules};
+use bevy::prelude::*;
+
+pub struct UIOptionsPlugin;
+
+impl Plugin for UIOptionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Options).with_system(options_menu))
+            .add_system_set(
+                SystemSet::on_update(AppState::Options)
+                    .with_system(options_key_input.label("input"))
+                    .with_system(redraw_options.after("input")),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Options).with_system(exit_ui_despawn));
+    }
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+fn shield_text(base: &BaseConfig) -> String {
+    if base.shield_seconds > 0.0 {
+        format!("{}s", base.shield_seconds)
+    } else {
+        "off".to_string()
+    }
+}
+
+fn options_text(
+    difficulty: &Difficulty,
+    mode: &PlayerMode,
+    game_mode: &GameMode,
+    rules: &Rules,
+) -> String {
+    format!(
+        "Options\n\n1: Difficulty: {}\n2: Deposit glod at base: {}\n3: Glod to bank for victory: {}\n4: Players: {}\n5: Mode: {}\n6: Enemy released {}\n7: Staged enemies: {}\n8: Destructible enemy base: {}\n9: Base shield: {}\n0: Base turret: {}\nW: Enemy waves: {}\nE: Enemy torpedoes: {}\nA: Asteroids: {}\nH: Hazards: {}\nF: Fog of war: {}\n\nEsc: return to main menu",
+        difficulty.name(),
+        on_off(rules.deposit.enabled),
+        rules.deposit.victory_minimum,
+        mode.name(),
+        game_mode.name(),
+        rules.release.describe(),
+        rules.release.stages,
+        on_off(rules.siege.enabled),
+        shield_text(&rules.base),
+        on_off(rules.base.turret),
+        on_off(rules.director.enabled),
+        on_off(rules.enemy_fire.enabled),
+        on_off(rules.asteroids.enabled),
+        on_off(rules.hazards.enabled),
+        on_off(rules.fog.enabled),
+    )
+}
+
+fn options_menu(
+    mut commands: Commands,
+    difficulty: Res<Difficulty>,
+    mode: Res<PlayerMode>,
+    game_mode: Res<GameMode>,
+    rules: Rules,
+    asset_server: Res<AssetServer>,
+) {
+    let text_alignment = TextAlignment::CENTER;
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                options_text(&difficulty, &mode, &game_mode, &rules),
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::WHITE,
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                },
+            )
+            .with_alignment(text_alignment),
+            ..default()
+        })
+        .insert(Ui);
+}
+
+fn redraw_options(
+    difficulty: Res<Difficulty>,
+    mode: Res<PlayerMode>,
+    game_mode: Res<GameMode>,
+    rules: Rules,
+    mut text: Query<&mut Text, With<Ui>>,
+) {
+    if !difficulty.is_changed()
+        && !mode.is_changed()
+        && !game_mode.is_changed()
+        && !rules.is_changed()
+    {
+        return;
+    }
+    for mut text in text.iter_mut() {
+        text.sections[0].value = options_text(&difficulty, &mode, &game_mode, &rules);
+    }
+}
+
+fn options_key_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut app_state: ResMut<State<AppState>>,
+    mut difficulty: ResMut<Difficulty>,
+    mut mode: ResMut<PlayerMode>,
+    mut game_mode: ResMut<GameMode>,
+    mut rules: Rules,
+) {
+    if keyboard_input.just_pressed(KeyCode::Key1) {
+        *difficulty = difficulty.next();
+    } else if keyboard_input.just_pressed(KeyCode::Key2) {
+        rules.deposit.enabled = !rules.deposit.enabled;
+    } else if keyboard_input.just_pressed(KeyCode::Key3) {
+        rules.deposit.victory_minimum = rules.deposit.next_minimum();
+    } else if keyboard_input.just_pressed(KeyCode::Key4) {
+        *mode = mode.next();
+    } else if keyboard_input.just_pressed(KeyCode::Key5) {
+        *game_mode = game_mode.next();
+    } else if keyboard_input.just_pressed(KeyCode::Key6) {
+        rules.release.trigger = rules.release.trigger.next();
+    } else if keyboard_input.just_pressed(KeyCode::Key7) {
+        rules.release.stages = rules.release.next_stages();
+    } else if keyboard_input.just_pressed(KeyCode::Key8) {
+        rules.siege.enabled = !rules.siege.enabled;
+    } else if keyboard_input.just_pressed(KeyCode::Key9) {
+        rules.base.shield_seconds = rules.base.next_shield_seconds();
+    } else if keyboard_input.just_pressed(KeyCode::Key0) {
+        rules.base.turret = !rules.base.turret;
+    } else if keyboard_input.just_pressed(KeyCode::W) {
+        rules.director.enabled = !rules.director.enabled;
+    } else if keyboard_input.just_pressed(KeyCode::E) {
+        rules.enemy_fire.enabled = !rules.enemy_fire.enabled;
+    } else if keyboard_input.just_pressed(KeyCode::A) {
+        rules.asteroids.enabled = !rules.asteroids.enabled;
+    } else if keyboard_input.just_pressed(KeyCode::H) {
+        rules.hazards.enabled = !rules.hazards.enabled;
+    } else if keyboard_input.just_pressed(KeyCode::F) {
+        rules.fog.enabled = !rules.fog.enabled;
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        app_state.set(AppState::MainMenu).unwrap();
+    };
+}
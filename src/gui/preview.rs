@@ -0,0 +1,66 @@
+use crate::gui::glod::{pickup_colour, tier_colour};
+use crate::{MapLayout, PICKUP_RADIUS, START_RADIUS};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude as lyon;
+
+/// Root of a scaled-down drawing of a map, with the shapes as its children.
+#[derive(Component)]
+pub struct MapPreview;
+
+/// Draws the arena, bases and glod of `layout` at `scale`, centred on `centre`.
+pub fn spawn_map_preview(
+    commands: &mut Commands,
+    layout: &MapLayout,
+    centre: Vec3,
+    scale: f32,
+) -> Entity {
+    let arena = lyon::GeometryBuilder::build_as(
+        &lyon::shapes::Rectangle {
+            extents: Vec2::splat(640.0),
+            origin: lyon::RectangleOrigin::Center,
+        },
+        lyon::DrawMode::Stroke(lyon::StrokeMode::new(Color::GRAY, 2.0 / scale)),
+        Transform::from_translation(centre).with_scale(Vec3::splat(scale)),
+    );
+    commands
+        .spawn((arena, MapPreview))
+        .with_children(|parent| {
+            let base = |pos: Vec3, colour: Color| {
+                lyon::GeometryBuilder::build_as(
+                    &lyon::shapes::Circle {
+                        radius: START_RADIUS,
+                        center: Vec2::ZERO,
+                    },
+                    lyon::DrawMode::Stroke(lyon::StrokeMode::new(colour, 2.0 / scale)),
+                    Transform::from_translation(pos),
+                )
+            };
+            parent.spawn(base(layout.friend, Color::TEAL));
+            parent.spawn(base(layout.foe, Color::RED));
+            for (glod, tier) in layout.glods.iter().zip(&layout.tiers) {
+                parent.spawn(lyon::GeometryBuilder::build_as(
+                    // Full-size glod would vanish at preview scale.
+                    &lyon::shapes::Circle {
+                        radius: f32::max(tier.radius(), 1.5 / scale),
+                        center: Vec2::ZERO,
+                    },
+                    lyon::DrawMode::Fill(lyon::FillMode::color(tier_colour(*tier))),
+                    Transform::from_translation(*glod),
+                ));
+            }
+            for (pos, pickup) in &layout.pickups {
+                parent.spawn(lyon::GeometryBuilder::build_as(
+                    &lyon::shapes::Circle {
+                        radius: f32::max(PICKUP_RADIUS, 3.0 / scale),
+                        center: Vec2::ZERO,
+                    },
+                    lyon::DrawMode::Stroke(lyon::StrokeMode::new(
+                        pickup_colour(*pickup),
+                        1.5 / scale,
+                    )),
+                    Transform::from_translation(*pos),
+                ));
+            }
+        })
+        .id()
+}
@@ -0,0 +1,103 @@
+use crate::{exit_ui_despawn, Ui};
+use crate::{AppState, Upgrades, Wallet, UPGRADES, UPGRADE_MAX_LEVEL};
+use bevy::prelude::*;
+
+pub struct UIShopPlugin;
+
+impl Plugin for UIShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Shop).with_system(shop_menu))
+            .add_system_set(
+                SystemSet::on_update(AppState::Shop)
+                    .with_system(shop_key_input.label("input"))
+                    .with_system(redraw_shop.after("input")),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Shop).with_system(exit_ui_despawn));
+    }
+}
+
+const UPGRADE_KEYS: [KeyCode; UPGRADES.len()] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+];
+
+fn shop_text(wallet: &Wallet, upgrades: &Upgrades) -> String {
+    let mut text = format!("Upgrades\nGlod: {}\n\n", wallet.0);
+    for (i, upgrade) in UPGRADES.iter().enumerate() {
+        let level = upgrades.level(*upgrade);
+        let price = if level >= UPGRADE_MAX_LEVEL {
+            "max".to_string()
+        } else {
+            upgrade.cost(level).to_string()
+        };
+        text += &format!(
+            "{}: {} {}/{} ({})\n",
+            i + 1,
+            upgrade.name(),
+            level,
+            UPGRADE_MAX_LEVEL,
+            price
+        );
+    }
+    text + "\nEnter: play\nEsc: return to main menu"
+}
+
+fn shop_menu(
+    mut commands: Commands,
+    wallet: Res<Wallet>,
+    upgrades: Res<Upgrades>,
+    asset_server: Res<AssetServer>,
+) {
+    let text_alignment = TextAlignment::CENTER;
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                shop_text(&wallet, &upgrades),
+                TextStyle {
+                    font_size: 36.0,
+                    color: Color::WHITE,
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                },
+            )
+            .with_alignment(text_alignment),
+            ..default()
+        })
+        .insert(Ui);
+}
+
+fn redraw_shop(
+    wallet: Res<Wallet>,
+    upgrades: Res<Upgrades>,
+    mut text: Query<&mut Text, With<Ui>>,
+) {
+    if !wallet.is_changed() && !upgrades.is_changed() {
+        return;
+    }
+    for mut text in text.iter_mut() {
+        text.sections[0].value = shop_text(&wallet, &upgrades);
+    }
+}
+
+fn shop_key_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut app_state: ResMut<State<AppState>>,
+    mut wallet: ResMut<Wallet>,
+    mut upgrades: ResMut<Upgrades>,
+) {
+    for (key, upgrade) in UPGRADE_KEYS.iter().zip(UPGRADES.iter()) {
+        if keyboard_input.just_pressed(*key) {
+            upgrades.buy(*upgrade, &mut wallet);
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        app_state.set(AppState::InGame).unwrap();
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        app_state.set(AppState::MainMenu).unwrap();
+    };
+}
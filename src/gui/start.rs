@@ -1,21 +1,29 @@
-use crate::{Drawn, EnemyBase, GameState, IsBase, START_RADIUS};
+use crate::{BaseHealth, BaseShield, Drawn, EnemyBase, GameState, IsBase, Team, START_RADIUS};
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude as lyon;
 
+const BASE_COLOURS: [Color; 2] = [Color::TEAL, Color::LIME_GREEN];
+
 pub struct UIStartPointPlugin;
 
 impl Plugin for UIStartPointPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(SystemSet::on_update(GameState::Game).with_system(draw_startpoints));
+        app.add_system_set(
+            SystemSet::on_update(GameState::Game)
+                .with_system(draw_startpoints)
+                .with_system(draw_base_damage)
+                .with_system(draw_shields)
+                .with_system(fade_shields),
+        );
     }
 }
 
 fn draw_startpoints(
     mut commands: Commands,
-    friend_q: Query<(Entity, &Transform), (With<IsBase>, Without<Drawn>)>,
+    friend_q: Query<(Entity, &Transform, &Team), (With<IsBase>, Without<Drawn>)>,
     enemy_q: Query<(Entity, &Transform), (With<EnemyBase>, Without<Drawn>)>,
 ) {
-    for ((friend, f_trans), (enemy, e_trans)) in friend_q.iter().zip(enemy_q.iter()) {
+    for (friend, f_trans, team) in friend_q.iter() {
         commands
             .entity(friend)
             .insert(Drawn)
@@ -25,12 +33,14 @@ fn draw_startpoints(
                     center: Vec2::ZERO,
                 },
                 lyon::DrawMode::Stroke(lyon::StrokeMode {
-                    color: Color::TEAL,
+                    color: BASE_COLOURS[team.0],
                     options: lyon::StrokeOptions::default().with_line_width(4.0),
                 }),
                 *f_trans,
             ));
+    }
 
+    for (enemy, e_trans) in enemy_q.iter() {
         commands
             .entity(enemy)
             .insert(Drawn)
@@ -47,3 +57,49 @@ fn draw_startpoints(
             ));
     }
 }
+
+/// Fades the enemy base from red to grey as it takes damage.
+fn draw_base_damage(
+    mut base: Query<(&BaseHealth, &mut lyon::DrawMode), (With<EnemyBase>, Changed<BaseHealth>)>,
+) {
+    for (health, mut draw_mode) in base.iter_mut() {
+        let t = health.fraction();
+        *draw_mode = lyon::DrawMode::Stroke(lyon::StrokeMode {
+            color: Color::rgb(0.3 + 0.7 * t, 0.3 * (1.0 - t), 0.3 * (1.0 - t)),
+            options: lyon::StrokeOptions::default().with_line_width(4.0),
+        });
+    }
+}
+
+fn shield_colour(team: &Team, alpha: f32) -> Color {
+    let mut colour = BASE_COLOURS[team.0];
+    colour.set_a(alpha);
+    colour
+}
+
+fn draw_shields(
+    mut commands: Commands,
+    shields: Query<(Entity, &Transform, &BaseShield, &Team), Without<Drawn>>,
+) {
+    for (entity, trans, shield, team) in shields.iter() {
+        commands
+            .entity(entity)
+            .insert(Drawn)
+            .insert(lyon::GeometryBuilder::build_as(
+                &lyon::shapes::Circle {
+                    radius: shield.radius,
+                    center: Vec2::ZERO,
+                },
+                lyon::DrawMode::Fill(lyon::FillMode::color(shield_colour(team, 0.25))),
+                *trans,
+            ));
+    }
+}
+
+/// Thins the shield out as its time runs down.
+fn fade_shields(mut shields: Query<(&BaseShield, &Team, &mut lyon::DrawMode), With<Drawn>>) {
+    for (shield, team, mut draw_mode) in shields.iter_mut() {
+        let alpha = 0.25 * shield.timer.percent_left();
+        *draw_mode = lyon::DrawMode::Fill(lyon::FillMode::color(shield_colour(team, alpha)));
+    }
+}
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+
+/// A single line of editable text with a cursor.
+#[derive(Clone, Debug)]
+pub struct TextInput {
+    pub value: String,
+    /// Position of the cursor in characters.
+    pub cursor: usize,
+    pub max_len: usize,
+    pub allowed: fn(char) -> bool,
+}
+
+impl TextInput {
+    pub fn new(value: &str, max_len: usize, allowed: fn(char) -> bool) -> TextInput {
+        let mut input = TextInput {
+            value: String::new(),
+            cursor: 0,
+            max_len,
+            allowed,
+        };
+        input.insert_str(value);
+        input
+    }
+
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.value.len(), |(i, _)| i)
+    }
+
+    fn len(&self) -> usize {
+        self.value.chars().count()
+    }
+
+    /// Inserts at the cursor, dropping disallowed characters and anything past the limit.
+    pub fn insert_str(&mut self, text: &str) {
+        for c in text.chars() {
+            if (self.allowed)(c) && self.len() < self.max_len {
+                let i = self.byte_index(self.cursor);
+                self.value.insert(i, c);
+                self.cursor += 1;
+            }
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            let i = self.byte_index(self.cursor);
+            self.value.remove(i);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.cursor < self.len() {
+            let i = self.byte_index(self.cursor);
+            self.value.remove(i);
+        }
+    }
+
+    /// Applies this frame's typing and editing keys; returns whether the text changed.
+    pub fn update(
+        &mut self,
+        chars: &mut EventReader<ReceivedCharacter>,
+        keyboard_input: &Input<KeyCode>,
+        paste: impl FnOnce() -> Option<String>,
+    ) -> bool {
+        let before = (self.value.clone(), self.cursor);
+        let ctrl = keyboard_input.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+        for ev in chars.iter() {
+            if !ctrl {
+                self.insert_str(&ev.char.to_string());
+            }
+        }
+
+        if ctrl && keyboard_input.just_pressed(KeyCode::V) {
+            if let Some(text) = paste() {
+                self.insert_str(text.trim());
+            }
+        }
+        if keyboard_input.just_pressed(KeyCode::Back) {
+            self.backspace();
+        }
+        if keyboard_input.just_pressed(KeyCode::Delete) {
+            self.delete();
+        }
+        if keyboard_input.just_pressed(KeyCode::Left) {
+            self.cursor = self.cursor.saturating_sub(1);
+        }
+        if keyboard_input.just_pressed(KeyCode::Right) {
+            self.cursor = usize::min(self.cursor + 1, self.len());
+        }
+        if keyboard_input.just_pressed(KeyCode::Home) {
+            self.cursor = 0;
+        }
+        if keyboard_input.just_pressed(KeyCode::End) {
+            self.cursor = self.len();
+        }
+        before != (self.value.clone(), self.cursor)
+    }
+
+    /// The text with a bar marking the cursor.
+    pub fn display(&self) -> String {
+        let mut shown = self.value.clone();
+        shown.insert(self.byte_index(self.cursor), '|');
+        shown
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{GameState, Tutorial};
+use bevy::prelude::*;
+
+pub struct UITutorialPlugin;
+
+impl Plugin for UITutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Game).with_system(spawn_prompt))
+            .add_system_set(SystemSet::on_update(GameState::Game).with_system(update_prompt))
+            .add_system_set(SystemSet::on_exit(GameState::Game).with_system(despawn_prompt));
+    }
+}
+
+#[derive(Component)]
+struct TutorialPrompt;
+
+fn spawn_prompt(mut commands: Commands, tutorial: Res<Tutorial>, asset_server: Res<AssetServer>) {
+    if !tutorial.active() {
+        return;
+    }
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::YELLOW,
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                },
+            )
+            .with_alignment(TextAlignment::BOTTOM_CENTER),
+            transform: Transform::from_xyz(0.0, -300.0, 1.0),
+            ..default()
+        },
+        TutorialPrompt,
+    ));
+}
+
+fn update_prompt(tutorial: Res<Tutorial>, mut prompt: Query<&mut Text, With<TutorialPrompt>>) {
+    if !tutorial.is_changed() {
+        return;
+    }
+    let value = tutorial.step.map_or("", |step| step.prompt());
+    for mut text in prompt.iter_mut() {
+        text.sections[0].value = value.to_string();
+    }
+}
+
+fn despawn_prompt(mut commands: Commands, prompt: Query<Entity, With<TutorialPrompt>>) {
+    for entity in prompt.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -1,10 +1,45 @@
 use crate::{
-    unit_movement_order, Body, BodyForce, Drawn, EnemyState, GameState, IsEnemy, Playable,
-    TorqueDirection, UNIT_FORCE_PROFILE, UNIT_POINTS,
+    spawn_glod_body, Body, BodyForce, Cargo, DepositRule, Difficulty, Dormant, Drawn,
+    EnemyArchetype, EnemyLauncher, EnemyState, GameState, GhostShip, IsEnemy, Pilot, Playable, PlayableCollider, RemoteControlled, Score,
+    ShipColour, ShipInput, ShipLost, SpeedBoost, Team, TorqueDirection, Upgrades, BOOST_FACTOR,
+    UNIT_FORCE_PROFILE, UNIT_MASS, UNIT_POINTS,
 };
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude as lyon;
 use bevy_rapier2d::prelude::*;
+use std::f32::consts::PI;
+
+const JETTISON_DISTANCE: f32 = 20.0;
+const JETTISON_SPEED: f32 = 40.0;
+
+pub struct ControlScheme {
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub thrust: KeyCode,
+    pub fire: KeyCode,
+    pub jettison: KeyCode,
+    pub ping: KeyCode,
+}
+
+/// Keys for each local player; the first is also used for solo play.
+pub const CONTROL_SCHEMES: [ControlScheme; 2] = [
+    ControlScheme {
+        left: KeyCode::Left,
+        right: KeyCode::Right,
+        thrust: KeyCode::Up,
+        fire: KeyCode::Space,
+        jettison: KeyCode::J,
+        ping: KeyCode::P,
+    },
+    ControlScheme {
+        left: KeyCode::A,
+        right: KeyCode::D,
+        thrust: KeyCode::W,
+        fire: KeyCode::LShift,
+        jettison: KeyCode::E,
+        ping: KeyCode::Q,
+    },
+];
 
 pub struct UIUnitPlugin;
 
@@ -14,25 +49,35 @@ impl Plugin for UIUnitPlugin {
             SystemSet::on_update(GameState::Game)
                 .with_system(map_edge)
                 .with_system(enemy_movement)
-                .with_system(unit_movement)
-                .with_system(draw_unit),
+                .with_system(read_controls.label("controls"))
+                .with_system(inputs_ready.label("inputs").after("controls"))
+                .with_system(unit_movement.after("inputs"))
+                .with_system(jettison.after("inputs"))
+                .with_system(draw_unit)
+                .with_system(draw_ghost)
+                .with_system(draw_cargo),
         );
     }
 }
 
-fn map_edge(mut game_state: ResMut<State<GameState>>, unit: Query<&Transform, With<Playable>>) {
-    let pos = unit.single().translation;
-    if (f32::abs(pos.x) > 342.0) || (f32::abs(pos.y) > 342.0) {
-        game_state.set(GameState::GameOver).unwrap();
-    };
+fn map_edge(mut lost: EventWriter<ShipLost>, unit: Query<(Entity, &Transform), With<Playable>>) {
+    for (ship, trans) in unit.iter() {
+        let pos = trans.translation;
+        if (f32::abs(pos.x) > 342.0) || (f32::abs(pos.y) > 342.0) {
+            lost.send(ShipLost(ship));
+        };
+    }
 }
 
 fn draw_unit(
     mut commands: Commands,
-    friend_q: Query<(Entity, &Transform), (With<Playable>, Without<Drawn>)>,
-    enemy_q: Query<(Entity, &Transform), (With<IsEnemy>, Without<Drawn>)>,
+    friend_q: Query<(Entity, &Transform, &ShipColour), (With<Playable>, Without<Drawn>)>,
+    enemy_q: Query<
+        (Entity, &Transform, Option<&EnemyArchetype>),
+        (With<IsEnemy>, Without<Drawn>),
+    >,
 ) {
-    for ((friend, f_trans), (enemy, e_trans)) in friend_q.iter().zip(enemy_q.iter()) {
+    for (friend, f_trans, colour) in friend_q.iter() {
         commands
             .entity(friend)
             .insert(Drawn)
@@ -41,10 +86,12 @@ fn draw_unit(
                     points: UNIT_POINTS.to_vec(),
                     closed: true,
                 },
-                lyon::DrawMode::Fill(lyon::FillMode::color(Color::BLUE)),
+                lyon::DrawMode::Fill(lyon::FillMode::color(colour.0)),
                 *f_trans,
             ));
+    }
 
+    for (enemy, e_trans, archetype) in enemy_q.iter() {
         commands
             .entity(enemy)
             .insert(Drawn)
@@ -53,55 +100,184 @@ fn draw_unit(
                     points: UNIT_POINTS.to_vec(),
                     closed: true,
                 },
-                lyon::DrawMode::Fill(lyon::FillMode::color(Color::RED)),
+                lyon::DrawMode::Fill(lyon::FillMode::color(enemy_colour(archetype))),
                 *e_trans,
             ));
     }
 }
 
+fn enemy_colour(archetype: Option<&EnemyArchetype>) -> Color {
+    match archetype.copied().unwrap_or_default() {
+        EnemyArchetype::Chaser => Color::RED,
+        EnemyArchetype::Sprinter => Color::ORANGE_RED,
+        EnemyArchetype::Brute => Color::MAROON,
+    }
+}
+
+fn draw_ghost(
+    mut commands: Commands,
+    ghosts: Query<(Entity, &Transform), (With<GhostShip>, Without<Drawn>)>,
+) {
+    for (ghost, trans) in ghosts.iter() {
+        commands
+            .entity(ghost)
+            .insert(Drawn)
+            .insert(lyon::GeometryBuilder::build_as(
+                &lyon::shapes::Polygon {
+                    points: UNIT_POINTS.to_vec(),
+                    closed: true,
+                },
+                lyon::DrawMode::Fill(lyon::FillMode::color(Color::rgba(1.0, 1.0, 1.0, 0.3))),
+                *trans,
+            ));
+    }
+}
+
 fn enemy_movement(
     player: Query<(&Transform, Option<&Velocity>), With<Playable>>,
-    enemy: Query<(&Transform, Option<&Velocity>), With<IsEnemy>>,
-    query: Query<(&Transform, &mut ExternalForce), With<IsEnemy>>,
+    mut enemies: Query<
+        (
+            &Transform,
+            Option<&Velocity>,
+            &mut ExternalForce,
+            Option<&Dormant>,
+            Option<&EnemyArchetype>,
+            Option<&EnemyLauncher>,
+        ),
+        With<IsEnemy>,
+    >,
     enemy_state: ResMut<State<EnemyState>>,
 ) {
-    let friend = Body::new(player.single());
-    let enemy = Body::new(enemy.single());
+    for (trans, vel, mut ext_force, dormant, archetype, launcher) in enemies.iter_mut() {
+        let enemy = Body::new((trans, vel));
+        let Some(friend) = player
+            .iter()
+            .map(Body::new)
+            .min_by(|a, b| enemy.distance(a).total_cmp(&enemy.distance(b)))
+        else {
+            return;
+        };
 
-    let (dir, angle) = enemy.bearing_of(&friend);
+        let (dir, angle) = enemy.bearing_of(&friend);
 
-    let forward = match enemy_state.current() {
-        EnemyState::Released => 1.0 / (1.0 + 2.0_f32.powf(angle)),
-        _ => 0.0,
-    };
+        let forward = match enemy_state.current() {
+            EnemyState::Released if dormant.is_none() => 1.0 / (1.0 + 2.0_f32.powf(angle)),
+            _ => 0.0,
+        } * launcher.map_or(1.0, EnemyLauncher::thrust_factor);
 
-    unit_movement_order(
-        BodyForce::new(dir * f32::min(angle, 0.4), forward, UNIT_FORCE_PROFILE),
-        query,
-    );
+        let profile = archetype.map_or(UNIT_FORCE_PROFILE, EnemyArchetype::force_profile);
+        BodyForce::new(dir * f32::min(angle, 0.4), forward, profile).apply(trans, &mut ext_force);
+    }
 }
 
-fn unit_movement(
+fn read_controls(
     keyboard_input: Res<Input<KeyCode>>,
-    query: Query<(&Transform, &mut ExternalForce), With<Playable>>,
+    remote: Query<(), With<RemoteControlled>>,
+    mut ships: Query<(&mut ShipInput, &Pilot), (With<Playable>, Without<RemoteControlled>)>,
 ) {
-    let mut torque = 0.0;
-    if keyboard_input.pressed(KeyCode::Left) {
-        torque = TorqueDirection::Left * 1.0;
-    } else if keyboard_input.pressed(KeyCode::Right) {
-        torque = TorqueDirection::Right * 1.0;
-    };
-
-    unit_movement_order(
-        BodyForce::new(
-            torque,
-            if keyboard_input.pressed(KeyCode::Up) {
-                1.0
-            } else {
-                0.0
-            },
-            UNIT_FORCE_PROFILE,
-        ),
-        query,
-    );
+    // In a networked game each peer flies its own ship with the first scheme.
+    let networked = !remote.is_empty();
+    for (mut input, pilot) in ships.iter_mut() {
+        let controls = &CONTROL_SCHEMES[if networked { 0 } else { pilot.0 }];
+        *input = ShipInput {
+            left: keyboard_input.pressed(controls.left),
+            right: keyboard_input.pressed(controls.right),
+            thrust: keyboard_input.pressed(controls.thrust),
+            fire: keyboard_input.just_released(controls.fire),
+            jettison: keyboard_input.just_pressed(controls.jettison),
+            ping: keyboard_input.just_pressed(controls.ping),
+        };
+    }
+}
+
+/// Anchors the "inputs" label after the keyboard, so systems reading
+/// `ShipInput` stay ordered when no replay or network session supplies it.
+fn inputs_ready() {}
+
+fn unit_movement(
+    upgrades: Res<Upgrades>,
+    mut query: Query<
+        (&Transform, &mut ExternalForce, &ShipInput, Option<&SpeedBoost>),
+        With<Playable>,
+    >,
+) {
+    for (trans, mut ext_force, input, boost) in query.iter_mut() {
+        let mut torque = 0.0;
+        if input.left {
+            torque = TorqueDirection::Left * 1.0;
+        } else if input.right {
+            torque = TorqueDirection::Right * 1.0;
+        };
+
+        let mut profile = upgrades.force_profile();
+        if boost.is_some() {
+            profile.forward *= BOOST_FACTOR;
+        }
+        BodyForce::new(torque, if input.thrust { 1.0 } else { 0.0 }, profile)
+        .apply(trans, &mut ext_force);
+    }
+}
+
+/// Blends a ship's colour towards gold as its load approaches the cap.
+fn load_colour(base: Color, fraction: f32) -> Color {
+    let t = fraction.clamp(0.0, 1.0);
+    Color::rgb(
+        (1.0 - t) * base.r() + t,
+        (1.0 - t) * base.g() + 0.84 * t,
+        (1.0 - t) * base.b(),
+    )
+}
+
+fn draw_cargo(
+    difficulty: Res<Difficulty>,
+    mut ships: Query<
+        (&Cargo, &ShipColour, &mut lyon::DrawMode),
+        (With<Playable>, Changed<Cargo>),
+    >,
+) {
+    for (cargo, colour, mut draw_mode) in ships.iter_mut() {
+        *draw_mode = lyon::DrawMode::Fill(lyon::FillMode::color(load_colour(
+            colour.0,
+            difficulty.load_fraction(cargo.weight()),
+        )));
+    }
+}
+
+fn jettison(
+    mut commands: Commands,
+    deposit: Res<DepositRule>,
+    mut score: ResMut<Score>,
+    mut ships: Query<
+        (Entity, &Transform, &Velocity, &mut Cargo, &ShipInput, &Team),
+        With<Playable>,
+    >,
+    mut collider: Query<(&Parent, &mut ColliderMassProperties), With<PlayableCollider>>,
+) {
+    for (ship, trans, velocity, mut cargo, input, team) in ships.iter_mut() {
+        if !input.jettison || cargo.0.is_empty() {
+            continue;
+        }
+
+        let count = cargo.0.len();
+        for (i, tier) in cargo.0.iter().enumerate() {
+            let spread = PI / 2.0 * ((i as f32 + 0.5) / count as f32 - 0.5);
+            let dir = -(Quat::from_rotation_z(spread) * trans.local_x()).truncate();
+            spawn_glod_body(
+                &mut commands,
+                trans.translation + (JETTISON_DISTANCE * dir).extend(0.0),
+                velocity.linvel + JETTISON_SPEED * dir,
+                *tier,
+            );
+        }
+
+        if !deposit.enabled {
+            score.0[team.0] = score.0[team.0].saturating_sub(cargo.value());
+        }
+        cargo.0.clear();
+        for (parent, mut props) in collider.iter_mut() {
+            if parent.get() == ship {
+                *props = ColliderMassProperties::Mass(UNIT_MASS);
+            }
+        }
+    }
 }
@@ -1,9 +1,10 @@
-use crate::{CollisionFilters, CollisionMemberships};
-use crate::{Drawn, Explosion, GameState, Playable, Torpedo, Weapon, WeaponPreLaunch, WEAPON_MASS};
+use crate::{
+    spawn_torpedo, Drawn, EnemyOrdnance, Explosion, GameState, Playable, ShipInput, ShipLaunched,
+    Team, Torpedo, Upgrades, WeaponPreLaunch,
+};
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude as lyon;
 use bevy_rapier2d::prelude::*;
-use bevy_rapier2d::geometry::Group;
 
 pub struct UIWeaponPlugin;
 
@@ -12,7 +13,8 @@ impl Plugin for UIWeaponPlugin {
         app.add_system_set(
             SystemSet::on_update(GameState::Game)
                 .with_system(draw_inert_weapons)
-                .with_system(standby_fire)
+                .with_system(draw_torpedoes)
+                .with_system(standby_fire.after("inputs"))
                 .with_system(draw_explosions),
         );
     }
@@ -37,51 +39,59 @@ fn draw_inert_weapons(
     }
 }
 
+fn draw_torpedoes(
+    mut commands: Commands,
+    torpedoes: Query<
+        (Entity, &Transform, Option<&EnemyOrdnance>),
+        (With<Torpedo>, Without<Drawn>),
+    >,
+) {
+    for (torpedo, trans, ordnance) in torpedoes.iter() {
+        let colour = if ordnance.is_some() {
+            Color::RED
+        } else {
+            Color::WHITE
+        };
+        commands
+            .entity(torpedo)
+            .insert(Drawn)
+            .insert(lyon::GeometryBuilder::build_as(
+                &lyon::shapes::Rectangle {
+                    extents: Vec2::new(5.0, 3.0),
+                    origin: lyon::shapes::RectangleOrigin::Center,
+                },
+                lyon::DrawMode::Fill(lyon::FillMode::color(colour)),
+                *trans,
+            ));
+    }
+}
+
 fn standby_fire(
-    keyboard_input: Res<Input<KeyCode>>,
     mut commands: Commands,
-    weapons: Query<(Entity, &Transform), With<WeaponPreLaunch>>,
-    unit: Query<(&Transform, &Velocity), With<Playable>>,
+    weapons: Query<(Entity, &Transform, &Parent), With<WeaponPreLaunch>>,
+    unit: Query<(Entity, &Transform, &Velocity, &ShipInput, &Team), With<Playable>>,
+    upgrades: Res<Upgrades>,
 ) {
-    let (trans, velocity) = unit.single();
-    match weapons.iter().last() {
-        Some((child, c_trans)) => {
-            if keyboard_input.just_released(KeyCode::Space) {
-                commands.entity(child).despawn();
+    for (ship, trans, velocity, input, team) in unit.iter() {
+        let stock = weapons.iter().filter(|(_, _, parent)| parent.get() == ship);
+        match stock.last() {
+            Some((child, c_trans, _)) => {
+                if input.fire {
+                    commands.entity(child).despawn();
 
-                commands
-                    .spawn((
-                    RigidBody::Dynamic,
-                    Damping {
-                        linear_damping: 0.2,
-                        angular_damping: 1.5,
-                    },
-                    Collider::cuboid(5.0, 3.0),
-                    CollisionGroups::new(
-                        Group::from_bits(CollisionMemberships::KineticWeapon as u32).unwrap(),
-                        Group::from_bits(CollisionFilters::KineticWeapon as u32).unwrap(),
-                    ),
-                    ColliderMassProperties::Mass(WEAPON_MASS),
-                    ActiveEvents::COLLISION_EVENTS,
-                    lyon::GeometryBuilder::build_as(
-                        &lyon::shapes::Rectangle {
-                            extents: Vec2::new(5.0, 3.0),
-                            origin: lyon::shapes::RectangleOrigin::Center,
-                        },
-                        lyon::DrawMode::Fill(lyon::FillMode::color(Color::WHITE)),
+                    let torpedo = spawn_torpedo(
+                        &mut commands,
                         (*trans) * (*c_trans),
-                    ),
-                    Weapon,
-                    ExternalForce {
-                        force: Vec2::ZERO,
-                        torque: 0.0,
-                    },
-                    *velocity,
-                    Torpedo::new()));
-            };
-        }
-        None => (),
-    };
+                        *velocity,
+                        Some(*team),
+                        Torpedo::new(upgrades.fuse(), upgrades.explosion()),
+                    );
+                    commands.entity(torpedo).insert(ShipLaunched);
+                };
+            }
+            None => (),
+        };
+    }
 }
 
 fn draw_explosions(
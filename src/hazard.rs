@@ -0,0 +1,146 @@
+//! Areas of the arena that push, pull and slow everything inside them.
+//!
+//! Hazards act on every dynamic body with an `ExternalForce`, but change its
+//! velocity directly: ships, enemies and torpedoes set their own force each
+//! frame, and glod never clears its own, so adding to it would either be lost
+//! or build up. Like gravity, the pull is the same whatever the mass.
+use crate::{
+    FoeStartingPoint, FriendStartingPoint, GameSeed, GameState, GameTime, HazardRule, PlayerMode,
+    RivalStartingPoint, DRIFT_EDGE, MAX_DRAWS, START_RADIUS,
+};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::prelude::*;
+use rand_pcg::Pcg64;
+use rand_seeder::Seeder;
+use std::f32::consts::TAU;
+
+pub struct HazardPlugin;
+
+impl Plugin for HazardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Game).with_system(spawn_hazards))
+            .add_system_set(SystemSet::on_update(GameState::Game).with_system(apply_hazards))
+            .add_system_set(SystemSet::on_enter(GameState::Victory).with_system(despawn_hazards))
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(despawn_hazards));
+    }
+}
+
+const HAZARD_RADIUS: std::ops::Range<f32> = 50.0..90.0;
+/// Hazards are kept at least this far from the edge of every base.
+const BASE_CLEARANCE: f32 = 20.0;
+/// Pull at the centre of a gravity well, and push at the centre of a repulsor.
+const WELL_STRENGTH: f32 = 120.0;
+const CURRENT_STRENGTH: f32 = 40.0;
+/// Share of its speed a body loses each second inside a nebula.
+const NEBULA_DAMPING: f32 = 2.5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HazardKind {
+    /// Pulls bodies towards its centre, harder the closer they get.
+    Well,
+    /// Pushes bodies away from its centre, harder the closer they get.
+    Repulsor,
+    /// Carries bodies along in one direction.
+    Current(Vec2),
+    /// Slows down anything inside it.
+    Nebula,
+}
+
+#[derive(Component, Clone)]
+pub struct Hazard {
+    pub kind: HazardKind,
+    pub radius: f32,
+}
+
+impl Hazard {
+    /// Change in velocity for a body at `offset` from the centre, moving at `linvel`.
+    fn acceleration(&self, offset: Vec2, linvel: Vec2) -> Vec2 {
+        let falloff = 1.0 - offset.length() / self.radius;
+        match self.kind {
+            HazardKind::Well => -WELL_STRENGTH * falloff * offset.normalize_or_zero(),
+            HazardKind::Repulsor => WELL_STRENGTH * falloff * offset.normalize_or_zero(),
+            HazardKind::Current(direction) => CURRENT_STRENGTH * direction,
+            HazardKind::Nebula => -NEBULA_DAMPING * linvel,
+        }
+    }
+}
+
+fn random_kind(rng: &mut Pcg64) -> HazardKind {
+    match rng.gen_range(0..4) {
+        0 => HazardKind::Well,
+        1 => HazardKind::Repulsor,
+        2 => HazardKind::Current(Vec2::from_angle(rng.gen_range(0.0..TAU))),
+        _ => HazardKind::Nebula,
+    }
+}
+
+fn spawn_hazards(
+    mut commands: Commands,
+    rule: Res<HazardRule>,
+    game_seed: Res<GameSeed>,
+    mode: Res<PlayerMode>,
+    friend: Res<FriendStartingPoint>,
+    rival: Res<RivalStartingPoint>,
+    foe: Res<FoeStartingPoint>,
+) {
+    if !rule.enabled {
+        return;
+    }
+    let mut rng: Pcg64 = Seeder::from(("hazards", &game_seed.0)).make_rng();
+    let mut bases = vec![friend.0, foe.0];
+    if mode.teams() > 1 {
+        bases.push(rival.0);
+    }
+    // Settles for fewer hazards if the bases leave too little room.
+    let mut placed = 0;
+    for _ in 0..MAX_DRAWS {
+        if placed >= rule.count {
+            break;
+        }
+        let radius = rng.gen_range(HAZARD_RADIUS);
+        let pos = Vec3::new(
+            rng.gen_range(-DRIFT_EDGE..DRIFT_EDGE),
+            rng.gen_range(-DRIFT_EDGE..DRIFT_EDGE),
+            0.0,
+        );
+        let clearance = START_RADIUS + BASE_CLEARANCE + radius;
+        if bases.iter().any(|base| base.distance(pos) < clearance) {
+            continue;
+        }
+        let kind = random_kind(&mut rng);
+        commands.spawn((
+            TransformBundle::from_transform(Transform::from_translation(pos)),
+            Hazard { kind, radius },
+        ));
+        placed += 1;
+    }
+}
+
+fn apply_hazards(
+    time: GameTime,
+    hazards: Query<(&Transform, &Hazard)>,
+    mut bodies: Query<(&Transform, &mut Velocity, &RigidBody), With<ExternalForce>>,
+) {
+    let dt = time.delta_seconds();
+    for (trans, mut velocity, body) in bodies.iter_mut() {
+        if *body != RigidBody::Dynamic {
+            continue;
+        }
+        let pos = trans.translation.truncate();
+        for (centre, hazard) in hazards.iter() {
+            let offset = pos - centre.translation.truncate();
+            if offset.length() > hazard.radius {
+                continue;
+            }
+            let linvel = velocity.linvel;
+            velocity.linvel += dt * hazard.acceleration(offset, linvel);
+        }
+    }
+}
+
+fn despawn_hazards(mut commands: Commands, hazards: Query<Entity, With<Hazard>>) {
+    for hazard in hazards.iter() {
+        commands.entity(hazard).despawn();
+    }
+}
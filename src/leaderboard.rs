@@ -0,0 +1,240 @@
+//! Best results for each game mode, kept between sessions.
+use crate::storage;
+use crate::{
+    counts, Cli, GameMode, GameSeed, GameState, PlayerMode, RoundClock, Score, Tutorial, GAME_MODES,
+};
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Leaderboards::from_storage())
+            .insert_resource(LastResult::default())
+            .add_system_set(
+                SystemSet::on_enter(GameState::Victory)
+                    .with_system(record_victory.label("record_result")),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver)
+                    .with_system(record_game_over.label("record_result")),
+            );
+    }
+}
+
+/// Entries kept per mode.
+pub const LEADERBOARD_SIZE: usize = 5;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LeaderboardEntry {
+    /// Milliseconds for timed modes, points otherwise.
+    pub value: u32,
+    /// Seed the result was set on; empty for results from before seeds were kept.
+    pub seed: String,
+}
+
+#[derive(Resource, Default)]
+pub struct Leaderboards(pub HashMap<GameMode, Vec<LeaderboardEntry>>);
+
+impl Leaderboards {
+    fn from_storage() -> Leaderboards {
+        let mut boards = Leaderboards::default();
+        for mode in GAME_MODES {
+            let stored = storage::load(&storage_key(mode)).unwrap_or_default();
+            let entries = stored
+                .split(',')
+                .filter_map(|entry| {
+                    let (value, seed) = entry.split_once(':')?;
+                    Some(LeaderboardEntry {
+                        value: value.parse().ok()?,
+                        seed: seed.to_string(),
+                    })
+                })
+                .collect();
+            boards.0.insert(mode, entries);
+        }
+        // Carry over the single high score kept before there were modes.
+        if boards.entries(GameMode::Classic).is_empty() {
+            if let Some(high_score) = storage::load("high_score").and_then(|s| s.parse().ok()) {
+                if high_score > 0 {
+                    boards.record(GameMode::Classic, high_score, "");
+                }
+            }
+        }
+        boards
+    }
+
+    fn save(&self, mode: GameMode) {
+        let stored = self
+            .entries(mode)
+            .iter()
+            .map(|entry| format!("{}:{}", entry.value, entry.seed))
+            .collect::<Vec<String>>()
+            .join(",");
+        storage::save(&storage_key(mode), &stored);
+    }
+
+    pub fn entries(&self, mode: GameMode) -> &[LeaderboardEntry] {
+        self.0.get(&mode).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn best(&self, mode: GameMode) -> Option<&LeaderboardEntry> {
+        self.entries(mode).first()
+    }
+
+    /// Adds a result, returning its place if it made the board.
+    ///
+    /// Seeds holding the separators of the stored list can't be saved, so their results are
+    /// turned away.
+    pub fn record(&mut self, mode: GameMode, value: u32, seed: &str) -> Option<usize> {
+        if seed.contains([',', ':']) {
+            return None;
+        }
+        let entries = self.0.entry(mode).or_default();
+        let rank = entries
+            .iter()
+            .position(|entry| {
+                if mode.lower_is_better() {
+                    value < entry.value
+                } else {
+                    value > entry.value
+                }
+            })
+            .unwrap_or(entries.len());
+        if rank >= LEADERBOARD_SIZE {
+            return None;
+        }
+        entries.insert(
+            rank,
+            LeaderboardEntry {
+                value,
+                seed: seed.to_string(),
+            },
+        );
+        entries.truncate(LEADERBOARD_SIZE);
+        Some(rank)
+    }
+}
+
+fn storage_key(mode: GameMode) -> String {
+    format!("leaderboard_{}", mode.key())
+}
+
+/// Result of the game that just ended, for the end screens.
+#[derive(Resource, Default)]
+pub struct LastResult {
+    pub value: Option<u32>,
+    /// Place on the leaderboard, counted from zero.
+    pub rank: Option<usize>,
+}
+
+fn record(
+    value: Option<u32>,
+    mode: GameMode,
+    players: PlayerMode,
+    seed: &GameSeed,
+    boards: &mut Leaderboards,
+    last: &mut LastResult,
+    counts: bool,
+) {
+    // Only runs that earn anything are ranked, and only solo ones.
+    if !counts || players != PlayerMode::Solo {
+        *last = LastResult::default();
+        return;
+    }
+    let rank = value.and_then(|value| boards.record(mode, value, &seed.0));
+    if rank.is_some() {
+        boards.save(mode);
+    }
+    *last = LastResult { value, rank };
+}
+
+fn record_victory(
+    mode: Res<GameMode>,
+    players: Res<PlayerMode>,
+    score: Res<Score>,
+    clock: Res<RoundClock>,
+    seed: Res<GameSeed>,
+    mut boards: ResMut<Leaderboards>,
+    mut last: ResMut<LastResult>,
+    cli: Res<Cli>,
+    tutorial: Res<Tutorial>,
+) {
+    let value = match *mode {
+        GameMode::Classic | GameMode::ScoreAttack => score.best(),
+        GameMode::TimeAttack | GameMode::Survival => clock.millis(),
+    };
+    record(
+        Some(value),
+        *mode,
+        *players,
+        &seed,
+        &mut boards,
+        &mut last,
+        counts(&cli, &tutorial),
+    );
+}
+
+/// Only survival is scored on losing; every other mode has to be won.
+fn record_game_over(
+    mode: Res<GameMode>,
+    players: Res<PlayerMode>,
+    clock: Res<RoundClock>,
+    seed: Res<GameSeed>,
+    mut boards: ResMut<Leaderboards>,
+    mut last: ResMut<LastResult>,
+    cli: Res<Cli>,
+    tutorial: Res<Tutorial>,
+) {
+    let value = match *mode {
+        GameMode::Survival => Some(clock.millis()),
+        _ => None,
+    };
+    record(
+        value,
+        *mode,
+        *players,
+        &seed,
+        &mut boards,
+        &mut last,
+        counts(&cli, &tutorial),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_ranks_and_trims_the_board() {
+        let mut boards = Leaderboards::default();
+        for points in [30, 10, 50, 20, 40] {
+            assert!(boards.record(GameMode::Classic, points, "seed").is_some());
+        }
+        assert_eq!(boards.record(GameMode::Classic, 35, "seed"), Some(2));
+        assert_eq!(boards.record(GameMode::Classic, 5, "seed"), None);
+        let values: Vec<u32> = boards
+            .entries(GameMode::Classic)
+            .iter()
+            .map(|entry| entry.value)
+            .collect();
+        assert_eq!(values, [50, 40, 35, 30, 20]);
+    }
+
+    #[test]
+    fn record_prefers_lower_times() {
+        let mut boards = Leaderboards::default();
+        boards.record(GameMode::TimeAttack, 9000, "a");
+        assert_eq!(boards.record(GameMode::TimeAttack, 8000, "b"), Some(0));
+        assert_eq!(boards.best(GameMode::TimeAttack).unwrap().seed, "b");
+    }
+
+    #[test]
+    fn record_turns_away_unstorable_seeds() {
+        let mut boards = Leaderboards::default();
+        assert_eq!(boards.record(GameMode::Classic, 10, "a,b"), None);
+        assert_eq!(boards.record(GameMode::Classic, 10, "a:b"), None);
+        assert!(boards.entries(GameMode::Classic).is_empty());
+    }
+}
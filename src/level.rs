@@ -0,0 +1,156 @@
+//! Hand-made maps, replacing the seeded layout.
+//!
+//! A level file has one entry per line, `<kind> <x> <y>`, where kind is
+//! `friend`, `foe` or `rival` for the bases, `glod` for each glod, or a
+//! pickup (`boost`, `shield` or `torpedoes`). A glod line may end with its
+//! tier, `common`, `rare` or `heavy`; it is common otherwise.
+//! Blank lines and lines starting with `#` are ignored. Bases that are not
+//! listed keep their seeded position, as do the glod and pickups if none are
+//! listed. A `pattern <name>` line picks how that seeded glod is laid out.
+use crate::{GlodPattern, GlodTier, Pickup};
+use bevy::prelude::*;
+use std::path::Path;
+
+#[derive(Resource, Default, Clone, Debug)]
+pub struct Level {
+    pub friend: Option<Vec3>,
+    pub foe: Option<Vec3>,
+    pub rival: Option<Vec3>,
+    pub glods: Vec<Vec3>,
+    /// Tier of each glod in `glods`.
+    pub tiers: Vec<GlodTier>,
+    pub pickups: Vec<(Vec3, Pickup)>,
+    pub pattern: Option<GlodPattern>,
+}
+
+impl Level {
+    pub fn load(path: &Path) -> Result<Level, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+        Level::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Level, String> {
+        let mut level = Level::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if let ["pattern", name] = fields[..] {
+                level.pattern = Some(
+                    GlodPattern::from_name(name)
+                        .ok_or_else(|| format!("line {}: unknown pattern {}", number + 1, name))?,
+                );
+                continue;
+            }
+            let (kind, x, y, tier) = match fields[..] {
+                [kind, x, y] => (kind, x, y, None),
+                ["glod", x, y, tier] => ("glod", x, y, Some(tier)),
+                _ => return Err(format!("line {}: expected <kind> <x> <y>", number + 1)),
+            };
+            let coord = |value: &str| {
+                value
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|v| v.abs() <= 320.0)
+                    .ok_or_else(|| format!("line {}: bad coordinate {}", number + 1, value))
+            };
+            let pos = Vec3::new(coord(x)?, coord(y)?, 0.0);
+            match kind {
+                "friend" => level.friend = Some(pos),
+                "foe" => level.foe = Some(pos),
+                "rival" => level.rival = Some(pos),
+                "glod" => {
+                    let tier = match tier {
+                        Some(name) => GlodTier::from_name(name)
+                            .ok_or_else(|| format!("line {}: unknown tier {}", number + 1, name))?,
+                        None => GlodTier::Common,
+                    };
+                    level.glods.push(pos);
+                    level.tiers.push(tier);
+                }
+                _ => match Pickup::from_name(kind) {
+                    Some(pickup) => level.pickups.push((pos, pickup)),
+                    None => return Err(format!("line {}: unknown kind {}", number + 1, kind)),
+                },
+            }
+        }
+        Ok(level)
+    }
+
+    /// The level as a file, one entry per line.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        if let Some(pattern) = self.pattern {
+            text += &format!("pattern {}\n", pattern.name());
+        }
+        let bases = [
+            ("friend", self.friend),
+            ("foe", self.foe),
+            ("rival", self.rival),
+        ];
+        for (kind, pos) in bases {
+            if let Some(pos) = pos {
+                text += &format!("{} {} {}\n", kind, pos.x, pos.y);
+            }
+        }
+        for (pos, tier) in self.glods.iter().zip(&self.tiers) {
+            text += &format!("glod {} {} {}\n", pos.x, pos.y, tier.name());
+        }
+        for (pos, pickup) in &self.pickups {
+            text += &format!("{} {} {}\n", pickup.name(), pos.x, pos.y);
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_places_bases_and_glod() {
+        let level = Level::parse("# bases\nfriend -200 -200\n\nfoe 200 200\nglod 0 10\n").unwrap();
+        assert_eq!(level.friend, Some(Vec3::new(-200.0, -200.0, 0.0)));
+        assert_eq!(level.foe, Some(Vec3::new(200.0, 200.0, 0.0)));
+        assert_eq!(level.rival, None);
+        assert_eq!(level.glods, [Vec3::new(0.0, 10.0, 0.0)]);
+    }
+
+    #[test]
+    fn parse_picks_a_glod_pattern() {
+        let level = Level::parse("pattern veins").unwrap();
+        assert_eq!(level.pattern, Some(GlodPattern::Veins));
+        assert!(level.glods.is_empty());
+    }
+
+    #[test]
+    fn parse_reads_tiers_and_pickups() {
+        let level = Level::parse("glod 0 10\nglod 0 20 rare\nshield 5 5\n").unwrap();
+        assert_eq!(level.tiers, [GlodTier::Common, GlodTier::Rare]);
+        assert_eq!(level.pickups, [(Vec3::new(5.0, 5.0, 0.0), Pickup::Shield)]);
+    }
+
+    #[test]
+    fn parse_reads_back_to_text() {
+        let text = "pattern rings\nfoe 200 -150.5\nglod 0 20 rare\nboost 5 5\n";
+        let level = Level::parse(text).unwrap();
+        let parsed = Level::parse(&level.to_text()).unwrap();
+        assert_eq!(parsed.pattern, level.pattern);
+        assert_eq!(parsed.foe, level.foe);
+        assert_eq!(parsed.glods, level.glods);
+        assert_eq!(parsed.tiers, level.tiers);
+        assert_eq!(parsed.pickups, level.pickups);
+    }
+
+    #[test]
+    fn parse_rejects_bad_lines() {
+        assert!(Level::parse("castle 0 0").is_err());
+        assert!(Level::parse("glod 0").is_err());
+        assert!(Level::parse("glod 0 1000").is_err());
+        assert!(Level::parse("pattern nowhere").is_err());
+        assert!(Level::parse("glod 0 0 golden").is_err());
+    }
+}
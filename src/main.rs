@@ -11,10 +11,19 @@ mod weapon;
 use weapon::*;
 
 mod gui;
+use gui::achievements::*;
+use gui::asteroid::*;
+use gui::browser::*;
 use gui::config::*;
+use gui::fog::*;
 use gui::glod::*;
+use gui::hazard::*;
+use gui::hud::*;
 use gui::menu::*;
+use gui::options::*;
+use gui::shop::*;
 use gui::start::*;
+use gui::tutorial::*;
 use gui::unit::*;
 use gui::weapon::*;
 
@@ -30,8 +39,77 @@ use physics::*;
 mod glod;
 use glod::*;
 
+mod distribution;
+use distribution::*;
+
+mod pickup;
+use pickup::*;
+
+mod game_mode;
+use game_mode::*;
+
+mod release;
+use release::*;
+
+mod siege;
+use siege::*;
+
+mod base;
+use base::*;
+
+mod director;
+use director::*;
+
+mod enemy_fire;
+use enemy_fire::*;
+
+mod asteroid;
+use asteroid::*;
+
+mod hazard;
+use hazard::*;
+
+mod fog;
+use fog::*;
+
+mod tutorial;
+use tutorial::*;
+
+mod achievement;
+use achievement::*;
+
+mod leaderboard;
+use leaderboard::*;
+
+mod upgrade;
+use upgrade::*;
+
+mod rules;
+use rules::*;
+
+mod storage;
+
+mod share;
+use share::*;
+
+mod cli;
+use cli::*;
+
+mod level;
+use level::*;
+
+mod replay;
+use replay::*;
+
+mod ghost;
+use ghost::*;
+
+#[cfg(not(target_family = "wasm"))]
+mod net;
+
 fn main() {
-    App::new()
+    let mut app = App::new();
+    app.insert_resource(Cli::from_args_or_exit())
         .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(
             PIXELS_PER_METER,
         ))
@@ -39,18 +117,54 @@ fn main() {
         .add_plugin(StatePlugin)
         .add_plugin(StartPointPlugin)
         .add_plugin(GlodPlugin)
+        .add_plugin(PickupPlugin)
         .add_plugin(RandPlugin)
         .add_plugin(UnitPlugin)
         .add_plugin(WeaponPlugin)
+        .add_plugin(UpgradePlugin)
+        .add_plugin(RulesPlugin)
+        .add_plugin(GameModePlugin)
+        .add_plugin(ReleasePlugin)
+        .add_plugin(SiegePlugin)
+        .add_plugin(BasePlugin)
+        .add_plugin(DirectorPlugin)
+        .add_plugin(EnemyFirePlugin)
+        .add_plugin(AsteroidPlugin)
+        .add_plugin(HazardPlugin)
+        .add_plugin(FogPlugin)
+        .add_plugin(TutorialPlugin)
+        .add_plugin(AchievementPlugin)
+        .add_plugin(LeaderboardPlugin)
+        .add_plugin(SharePlugin)
+        .add_plugin(CliPlugin)
+        .add_plugin(ReplayPlugin)
+        .add_plugin(GhostPlugin)
         .add_plugin(UIConfigPlugin)
         .add_plugin(UIWeaponPlugin)
         .add_plugin(UIStartPointPlugin)
         .add_plugin(UIGlodPlugin)
+        .add_plugin(UIAsteroidPlugin)
+        .add_plugin(UIHazardPlugin)
+        .add_plugin(UIFogPlugin)
+        .add_plugin(UITutorialPlugin)
         .add_plugin(UIUnitPlugin)
+        .add_plugin(UIHudPlugin)
         .add_plugin(UIMenuPlugin)
-        .run();
+        .add_plugin(UIShopPlugin)
+        .add_plugin(UIOptionsPlugin)
+        .add_plugin(UIBrowserPlugin)
+        .add_plugin(UIAchievementsPlugin);
+    #[cfg(not(target_family = "wasm"))]
+    app.add_plugin(net::NetPlugin);
+    app.run();
 }
 
-fn rapier_setup(mut rapier_config: ResMut<RapierConfiguration>) {
+fn rapier_setup(cli: Res<Cli>, mut rapier_config: ResMut<RapierConfiguration>) {
     rapier_config.gravity = Vec2::new(0.0, 0.0);
+    if cli.needs_fixed_timestep() {
+        rapier_config.timestep_mode = TimestepMode::Fixed {
+            dt: FRAME_DT,
+            substeps: 1,
+        };
+    }
 }
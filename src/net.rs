@@ -0,0 +1,951 @@
+//! Online versus play over UDP with rollback.
+//!
+//! Each peer sends its own inputs for every frame, `INPUT_DELAY` frames ahead
+//! of when they are applied, and predicts the other peer's inputs until they
+//! arrive. The game's update and physics stages run inside `RollbackStage`,
+//! which snapshots everything simulated at the start of each frame. When a
+//! prediction turns out wrong the world is restored from the snapshot taken
+//! at that frame and every frame since is played again, one at a time, with
+//! the inputs now known for it.
+//!
+//! A peer that gets more than `MAX_ROLLBACK` frames ahead of the other's
+//! confirmed inputs waits for them, which also covers the host starting its
+//! round first. The game only ends on a frame whose inputs are all known, and
+//! losing a snapshot that a rollback needs drops the connection.
+//!
+//! Start one instance with `--host <port>` and another with
+//! `--join <addr:port>`; `--net-latency <ms>` and `--net-loss <percent>` put a
+//! simulated bad link between them.
+use crate::{
+    AppState, Asteroid, BaseHealth, BaseShield, BaseTurret, Blasted, Cargo, Cli, Defender,
+    DefenderTimer, Dormant, Drifting, EndGate, EnemyArchetype, EnemyBase, EnemyLauncher,
+    EnemyOrdnance, EnemyState, Explodee, Explosion, Flare, GameSeed, GameState, GlodTier, Hazard,
+    IsBase, IsEnemy, IsGlod, Pickup, Pilot, Playable, PlayableCollider, PlayerMode,
+    RemoteControlled, RoundClock, RoundStats, Score, Shielded, ShipColour, ShipInput, ShipLaunched,
+    Sonar, SpeedBoost, Team, Torpedo, Wave, WaveDirector, Weapon, WeaponLaunch, WeaponPreLaunch,
+    Winner, FRAME_DT,
+};
+use bevy::ecs::event::ManualEventReader;
+use bevy::hierarchy::despawn_with_children_recursive;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::prelude::*;
+use rand_pcg::Pcg64;
+use rand_seeder::Seeder;
+use std::any::Any;
+use std::collections::{BTreeMap, VecDeque};
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Frames between reading an input and applying it, to hide the round trip.
+const INPUT_DELAY: u32 = 2;
+/// Frames of local input repeated in every packet, covering for lost ones.
+const REDUNDANT_INPUTS: u32 = 8;
+/// How far back a misprediction can still be rolled back, and so how far
+/// ahead of the other peer's inputs the simulation may run.
+const MAX_ROLLBACK: u32 = 30;
+const MAX_PACKET: usize = 512;
+
+const PACKET_JOIN: u8 = 0;
+const PACKET_HELLO: u8 = 1;
+const PACKET_INPUTS: u8 = 2;
+
+pub trait Transport: Send + Sync {
+    fn send(&mut self, bytes: &[u8]);
+    fn recv(&mut self) -> Option<Vec<u8>>;
+}
+
+/// Non-blocking UDP socket; the host learns its peer from the first packet.
+pub struct UdpTransport {
+    socket: UdpSocket,
+    peer: Option<SocketAddr>,
+}
+
+impl UdpTransport {
+    pub fn host(port: u16) -> std::io::Result<UdpTransport> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))?;
+        socket.set_nonblocking(true)?;
+        Ok(UdpTransport { socket, peer: None })
+    }
+
+    pub fn join(addr: &str) -> std::io::Result<UdpTransport> {
+        let peer: SocketAddr = addr
+            .parse()
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, addr.to_string()))?;
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.set_nonblocking(true)?;
+        Ok(UdpTransport {
+            socket,
+            peer: Some(peer),
+        })
+    }
+}
+
+impl Transport for UdpTransport {
+    fn send(&mut self, bytes: &[u8]) {
+        if let Some(peer) = self.peer {
+            let _ = self.socket.send_to(bytes, peer);
+        }
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        let mut buf = [0; MAX_PACKET];
+        loop {
+            let (len, from) = self.socket.recv_from(&mut buf).ok()?;
+            match self.peer {
+                None => self.peer = Some(from),
+                Some(peer) if peer != from => continue,
+                _ => (),
+            }
+            return Some(buf[..len].to_vec());
+        }
+    }
+}
+
+/// Delays and drops outgoing packets to simulate a poor connection.
+pub struct LinkShim<T: Transport> {
+    inner: T,
+    latency: Duration,
+    loss: f32,
+    rng: Pcg64,
+    queue: VecDeque<(Instant, Vec<u8>)>,
+}
+
+impl<T: Transport> LinkShim<T> {
+    pub fn new(inner: T, latency: Duration, loss: f32) -> LinkShim<T> {
+        LinkShim {
+            inner,
+            latency,
+            loss,
+            rng: Seeder::from("link shim").make_rng(),
+            queue: VecDeque::new(),
+        }
+    }
+
+    fn flush(&mut self) {
+        let now = Instant::now();
+        while let Some((due, _)) = self.queue.front() {
+            if *due > now {
+                break;
+            }
+            let (_, bytes) = self.queue.pop_front().unwrap();
+            self.inner.send(&bytes);
+        }
+    }
+}
+
+impl<T: Transport> Transport for LinkShim<T> {
+    fn send(&mut self, bytes: &[u8]) {
+        if self.rng.gen::<f32>() >= self.loss {
+            self.queue
+                .push_back((Instant::now() + self.latency, bytes.to_vec()));
+        }
+        self.flush();
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        self.flush();
+        self.inner.recv()
+    }
+}
+
+enum Packet {
+    Join,
+    Hello { seed: String },
+    Inputs { first: u32, inputs: Vec<u8> },
+}
+
+impl Packet {
+    fn encode(&self) -> Vec<u8> {
+        match self {
+            Packet::Join => vec![PACKET_JOIN],
+            Packet::Hello { seed } => {
+                let mut bytes = vec![PACKET_HELLO];
+                bytes.extend_from_slice(seed.as_bytes());
+                bytes
+            }
+            Packet::Inputs { first, inputs } => {
+                let mut bytes = vec![PACKET_INPUTS];
+                bytes.extend_from_slice(&first.to_le_bytes());
+                bytes.extend_from_slice(inputs);
+                bytes
+            }
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Packet> {
+        match *bytes.first()? {
+            PACKET_JOIN => Some(Packet::Join),
+            PACKET_HELLO => Some(Packet::Hello {
+                seed: String::from_utf8(bytes[1..].to_vec())
+                    .ok()
+                    .filter(|seed| GameSeed::is_valid(seed))?,
+            }),
+            PACKET_INPUTS if bytes.len() >= 5 => Some(Packet::Inputs {
+                first: u32::from_le_bytes(bytes[1..5].try_into().unwrap()),
+                inputs: bytes[5..].to_vec(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Everything the game simulates, as it was at the start of a frame.
+struct Snapshot {
+    frame: u32,
+    entities: Vec<Entity>,
+    parents: Vec<Option<Entity>>,
+    /// One entry per `Saver` in the registry.
+    saved: Vec<Box<dyn Any + Send + Sync>>,
+    enemy_state: Option<EnemyState>,
+    physics: Option<Vec<u8>>,
+    /// Collisions from the previous frame's step, not yet seen by the game.
+    collisions: Vec<CollisionEvent>,
+}
+
+/// Entities that take part in the simulation; everything else is left alone.
+type Simulated = Or<(
+    With<RigidBody>,
+    With<Collider>,
+    With<Explosion>,
+    With<Flare>,
+    With<Hazard>,
+    With<BaseShield>,
+)>;
+
+impl Snapshot {
+    fn save(
+        world: &mut World,
+        registry: &[Saver],
+        frame: u32,
+        collisions: Vec<CollisionEvent>,
+    ) -> Snapshot {
+        let entities: Vec<Entity> = world
+            .query_filtered::<Entity, Simulated>()
+            .iter(world)
+            .collect();
+        Snapshot {
+            frame,
+            parents: entities
+                .iter()
+                .map(|entity| world.get::<Parent>(*entity).map(Parent::get))
+                .collect(),
+            saved: registry
+                .iter()
+                .map(|saver| (saver.save)(world, &entities))
+                .collect(),
+            enemy_state: world
+                .get_resource::<State<EnemyState>>()
+                .map(|state| state.current().clone()),
+            physics: world.get_resource::<RapierContext>().map(|context| {
+                bincode::serialize(context).expect("could not save the physics world")
+            }),
+            collisions,
+            entities,
+        }
+    }
+
+    fn restore(&self, world: &mut World, registry: &[Saver]) {
+        let current: Vec<Entity> = world
+            .query_filtered::<Entity, Simulated>()
+            .iter(world)
+            .collect();
+        for entity in current {
+            if !self.entities.contains(&entity) && world.get_entity(entity).is_some() {
+                despawn_with_children_recursive(world, entity);
+            }
+        }
+        for entity in &self.entities {
+            if world.get_or_spawn(*entity).is_none() {
+                warn!("could not roll back {:?}, its id is in use", entity);
+            }
+        }
+        for (saver, saved) in registry.iter().zip(&self.saved) {
+            (saver.load)(world, &self.entities, saved.as_ref());
+        }
+
+        for (child, parent) in self.entities.iter().zip(&self.parents) {
+            let current = world.get::<Parent>(*child).map(Parent::get);
+            if current == *parent || world.get_entity(*child).is_none() {
+                continue;
+            }
+            if let Some(old) = current {
+                world.entity_mut(old).remove_children(&[*child]);
+            }
+            if let Some(parent) = parent.filter(|p| world.get_entity(*p).is_some()) {
+                world.entity_mut(parent).push_children(&[*child]);
+            }
+        }
+
+        if let Some(saved) = &self.enemy_state {
+            let mut state = world.resource_mut::<State<EnemyState>>();
+            if state.current() != saved {
+                let _ = state.overwrite_replace(saved.clone());
+            }
+        }
+        if let Some(bytes) = &self.physics {
+            let context: RapierContext =
+                bincode::deserialize(bytes).expect("could not restore the physics world");
+            world.insert_resource(context);
+        }
+        if let Some(mut events) = world.get_resource_mut::<Events<CollisionEvent>>() {
+            events.clear();
+            events.extend(self.collisions.iter().copied());
+        }
+    }
+}
+
+/// Copies one kind of component or resource in and out of a `Snapshot`.
+struct Saver {
+    save: fn(&World, &[Entity]) -> Box<dyn Any + Send + Sync>,
+    load: fn(&mut World, &[Entity], &(dyn Any + Send + Sync)),
+}
+
+fn component<C: Component + Clone>() -> Saver {
+    Saver {
+        save: |world, entities| {
+            let saved: Vec<Option<C>> = entities
+                .iter()
+                .map(|entity| world.get::<C>(*entity).cloned())
+                .collect();
+            Box::new(saved)
+        },
+        load: |world, entities, saved| {
+            let saved = saved.downcast_ref::<Vec<Option<C>>>().unwrap();
+            for (entity, saved) in entities.iter().zip(saved) {
+                let Some(mut entity) = world.get_entity_mut(*entity) else {
+                    continue;
+                };
+                match saved {
+                    // Overwritten quietly, so change detection only sees what
+                    // the simulation itself does.
+                    Some(saved) if entity.contains::<C>() => {
+                        let mut current = entity.get_mut::<C>().unwrap();
+                        *current.bypass_change_detection() = saved.clone();
+                    }
+                    Some(saved) => {
+                        entity.insert(saved.clone());
+                    }
+                    None => {
+                        entity.remove::<C>();
+                    }
+                }
+            }
+        },
+    }
+}
+
+fn resource<R: Resource + Clone>() -> Saver {
+    Saver {
+        save: |world, _| Box::new(world.get_resource::<R>().cloned()),
+        load: |world, _, saved| {
+            if let Some(saved) = saved.downcast_ref::<Option<R>>().unwrap() {
+                world.insert_resource(saved.clone());
+            }
+        },
+    }
+}
+
+/// The state rolled back on a misprediction. Drawing isn't included, so
+/// entities brought back are drawn afresh.
+fn registry() -> Vec<Saver> {
+    vec![
+        component::<Transform>(),
+        component::<GlobalTransform>(),
+        component::<RigidBody>(),
+        component::<Velocity>(),
+        component::<ExternalForce>(),
+        component::<ExternalImpulse>(),
+        component::<Damping>(),
+        component::<Collider>(),
+        component::<Sensor>(),
+        component::<CollisionGroups>(),
+        component::<ColliderMassProperties>(),
+        component::<Restitution>(),
+        component::<ActiveEvents>(),
+        component::<RapierRigidBodyHandle>(),
+        component::<RapierColliderHandle>(),
+        component::<Playable>(),
+        component::<PlayableCollider>(),
+        component::<Pilot>(),
+        component::<ShipColour>(),
+        component::<ShipInput>(),
+        component::<RemoteControlled>(),
+        component::<Cargo>(),
+        component::<SpeedBoost>(),
+        component::<Shielded>(),
+        component::<Team>(),
+        component::<IsEnemy>(),
+        component::<EnemyArchetype>(),
+        component::<Dormant>(),
+        component::<Defender>(),
+        component::<EnemyLauncher>(),
+        component::<EnemyOrdnance>(),
+        component::<Blasted>(),
+        component::<IsGlod>(),
+        component::<GlodTier>(),
+        component::<Drifting>(),
+        component::<Pickup>(),
+        component::<IsBase>(),
+        component::<EnemyBase>(),
+        component::<BaseTurret>(),
+        component::<BaseShield>(),
+        component::<BaseHealth>(),
+        component::<Weapon>(),
+        component::<WeaponPreLaunch>(),
+        component::<WeaponLaunch>(),
+        component::<Explodee>(),
+        component::<Torpedo>(),
+        component::<ShipLaunched>(),
+        component::<Explosion>(),
+        component::<Asteroid>(),
+        component::<Hazard>(),
+        component::<Flare>(),
+        resource::<Time>(),
+        resource::<Score>(),
+        resource::<Winner>(),
+        resource::<RoundClock>(),
+        resource::<Wave>(),
+        resource::<WaveDirector>(),
+        resource::<DefenderTimer>(),
+        resource::<Sonar>(),
+        resource::<RoundStats>(),
+    ]
+}
+
+#[derive(Resource)]
+pub struct NetSession {
+    transport: Box<dyn Transport>,
+    hosting: bool,
+    connected: bool,
+    /// Seed agreed at connection; later rounds derive theirs from it.
+    seed: Option<String>,
+    round: u32,
+    pub local: usize,
+    /// Next new frame to simulate.
+    frame: u32,
+    /// Frame being simulated right now, behind `frame` while catching up.
+    sim_frame: u32,
+    /// Game time, which only moves on with the simulated frames.
+    sim_time: Option<Time>,
+    local_inputs: BTreeMap<u32, u8>,
+    remote_inputs: BTreeMap<u32, u8>,
+    predicted: BTreeMap<u32, u8>,
+    /// First frame whose remote input hasn't arrived yet.
+    unconfirmed: u32,
+    snapshots: VecDeque<Snapshot>,
+    rollback: Option<u32>,
+    /// Earliest frame that would have ended the game on guessed inputs.
+    held_end: Option<u32>,
+}
+
+impl NetSession {
+    fn new(transport: Box<dyn Transport>, hosting: bool) -> NetSession {
+        NetSession {
+            transport,
+            hosting,
+            connected: false,
+            seed: None,
+            round: 0,
+            local: if hosting { 0 } else { 1 },
+            frame: 0,
+            sim_frame: 0,
+            sim_time: None,
+            local_inputs: BTreeMap::new(),
+            remote_inputs: BTreeMap::new(),
+            predicted: BTreeMap::new(),
+            unconfirmed: 0,
+            snapshots: VecDeque::new(),
+            rollback: None,
+            held_end: None,
+        }
+    }
+
+    fn send(&mut self, packet: Packet) {
+        self.transport.send(&packet.encode());
+    }
+
+    /// Schedules the keyboard input read on `frame`.
+    fn record_local(&mut self, frame: u32, bits: u8) {
+        self.local_inputs.insert(frame + INPUT_DELAY, bits);
+    }
+
+    /// Sends the latest local inputs, repeating recent ones in case of loss.
+    fn send_inputs(&mut self, frame: u32) {
+        let last = frame + INPUT_DELAY;
+        let first = last.saturating_sub(REDUNDANT_INPUTS - 1);
+        let inputs: Vec<u8> = (first..=last)
+            .map(|f| self.local_inputs.get(&f).copied().unwrap_or(0))
+            .collect();
+        self.send(Packet::Inputs { first, inputs });
+    }
+
+    fn poll(&mut self) {
+        while let Some(bytes) = self.transport.recv() {
+            match Packet::decode(&bytes) {
+                Some(Packet::Inputs { first, inputs }) => self.receive_inputs(first, &inputs),
+                Some(Packet::Join) if self.hosting => {
+                    // The joining peer missed our hello and is still waiting.
+                    let seed = self.seed.clone().unwrap_or_default();
+                    self.send(Packet::Hello { seed });
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Local and remote inputs for `frame`, guessing the remote one if it
+    /// hasn't arrived yet.
+    fn inputs_at(&mut self, frame: u32) -> (u8, u8) {
+        let local = self.local_inputs.get(&frame).copied().unwrap_or(0);
+        let remote = match self.remote_inputs.get(&frame) {
+            Some(bits) => *bits,
+            None => {
+                let guess = self.predict(frame);
+                self.predicted.insert(frame, guess);
+                guess
+            }
+        };
+        (local, remote)
+    }
+
+    /// Repeats the last confirmed input, without one-off presses.
+    fn predict(&self, frame: u32) -> u8 {
+        let held = ShipInput {
+            fire: false,
+            jettison: false,
+            ping: false,
+            ..ShipInput::from_bits(
+                self.remote_inputs
+                    .range(..frame)
+                    .next_back()
+                    .map_or(0, |(_, bits)| *bits),
+            )
+        };
+        held.to_bits()
+    }
+
+    fn receive_inputs(&mut self, first: u32, inputs: &[u8]) {
+        for (frame, bits) in (first..).zip(inputs.iter().copied()) {
+            if self.remote_inputs.insert(frame, bits).is_some() {
+                continue;
+            }
+            if matches!(self.predicted.get(&frame), Some(guess) if *guess != bits) {
+                self.rollback = Some(self.rollback.map_or(frame, |r| r.min(frame)));
+            }
+        }
+        while self.remote_inputs.contains_key(&self.unconfirmed) {
+            self.unconfirmed += 1;
+        }
+    }
+
+    fn forget_before(&mut self, frame: u32) {
+        self.local_inputs = self.local_inputs.split_off(&frame);
+        self.remote_inputs = self.remote_inputs.split_off(&frame);
+        self.predicted = self.predicted.split_off(&frame);
+        while matches!(self.snapshots.front(), Some(s) if s.frame < frame) {
+            self.snapshots.pop_front();
+        }
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, StageLabel)]
+struct Rollback;
+
+/// Runs the game's update and physics stages, once per frame normally and
+/// again for every frame since a misprediction when rolling back.
+struct RollbackStage {
+    sim: Schedule,
+    registry: Vec<Saver>,
+    collisions: ManualEventReader<CollisionEvent>,
+    /// Collisions from the last step, handed to the next snapshot.
+    pending: Vec<CollisionEvent>,
+}
+
+impl RollbackStage {
+    /// Ends the round once the other peer can no longer be kept in step.
+    fn drop_out(&mut self, world: &mut World) {
+        world
+            .resource_mut::<State<GameState>>()
+            .overwrite_set(GameState::GameOver)
+            .unwrap();
+        self.sim.run_once(world);
+    }
+}
+
+impl Stage for RollbackStage {
+    fn run(&mut self, world: &mut World) {
+        if *world.resource::<State<GameState>>().current() != GameState::Game {
+            self.sim.run_once(world);
+            return;
+        }
+        if !world.resource::<NetSession>().connected {
+            self.drop_out(world);
+            return;
+        }
+        let real_time = world.resource::<Time>().clone();
+
+        let mut session = world.remove_resource::<NetSession>().unwrap();
+        session.poll();
+        // The inputs behind a held back end are known now, so play it again.
+        if let Some(held) = session.held_end.filter(|held| *held < session.unconfirmed) {
+            session.rollback = Some(session.rollback.map_or(held, |r| r.min(held)));
+            session.held_end = None;
+        }
+        world.insert_resource(session.sim_time.take().unwrap_or_default());
+        let newest = session.frame;
+        // Too far ahead to roll back in time, so wait for the other peer.
+        let stalled = newest >= session.unconfirmed + MAX_ROLLBACK;
+        let end = if stalled { newest } else { newest + 1 };
+        let mut first = end;
+        if let Some(rollback) = session.rollback.take() {
+            let Some(snapshot) = session.snapshots.iter().find(|s| s.frame == rollback) else {
+                error!(
+                    "lost sync with the other player: no snapshot of frame {} to roll back to",
+                    rollback
+                );
+                session.connected = false;
+                world.insert_resource(session);
+                world.insert_resource(real_time);
+                self.drop_out(world);
+                return;
+            };
+            snapshot.restore(world, &self.registry);
+            self.collisions = world
+                .resource::<Events<CollisionEvent>>()
+                .get_reader_current();
+            self.pending = snapshot.collisions.clone();
+            first = rollback;
+            session.held_end = session.held_end.filter(|held| *held < first);
+        }
+        if stalled {
+            // Our inputs may be what the other peer is waiting for.
+            session.send_inputs(newest - 1);
+        }
+        session.snapshots.retain(|s| s.frame < first);
+        world.insert_resource(session);
+
+        for frame in first..end {
+            let snapshot = Snapshot::save(world, &self.registry, frame, self.pending.clone());
+            let mut session = world.resource_mut::<NetSession>();
+            session.sim_frame = frame;
+            session.snapshots.push_back(snapshot);
+            let predicted = frame >= session.unconfirmed;
+            world.resource_mut::<EndGate>().predicted = predicted;
+            advance(&mut world.resource_mut::<Time>());
+
+            self.sim.run_once(world);
+
+            let events = world.resource::<Events<CollisionEvent>>();
+            self.pending = self.collisions.iter(events).copied().collect();
+            if std::mem::take(&mut world.resource_mut::<EndGate>().held) {
+                let mut session = world.resource_mut::<NetSession>();
+                session.held_end = Some(session.held_end.map_or(frame, |held| held.min(frame)));
+            }
+            if *world.resource::<State<GameState>>().current() != GameState::Game {
+                break;
+            }
+        }
+        world.resource_mut::<EndGate>().predicted = false;
+
+        let sim_time = world.resource::<Time>().clone();
+        world.insert_resource(real_time);
+        let mut session = world.resource_mut::<NetSession>();
+        session.sim_time = Some(sim_time);
+        session.frame = end;
+        session.forget_before(end.saturating_sub(MAX_ROLLBACK));
+    }
+}
+
+fn advance(time: &mut Time) {
+    let last = time.last_update().unwrap_or_else(|| time.startup());
+    time.update_with_instant(last + Duration::from_secs_f32(FRAME_DT));
+}
+
+/// Moves a stage out of the app and into the simulation.
+fn take_stage(app: &mut App, sim: &mut Schedule, label: impl StageLabel + Clone) {
+    let stage = app
+        .schedule
+        .get_stage_mut::<SystemStage>(label.clone())
+        .expect("simulation stage missing");
+    sim.add_stage(label, std::mem::replace(stage, SystemStage::parallel()));
+}
+
+pub struct NetPlugin;
+
+impl Plugin for NetPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(session) = session_from_cli(app.world.resource::<Cli>()) else {
+            return;
+        };
+        app.insert_resource(session)
+            .add_system_set(SystemSet::on_update(AppState::MainMenu).with_system(handshake))
+            .add_system_set(
+                SystemSet::on_enter(GameState::SampleRandom)
+                    .with_system(round_seed.before("game_resources")),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(reset_frames))
+            .add_system_set(
+                SystemSet::on_update(GameState::Game)
+                    .with_system(mark_remote.before("inputs"))
+                    .with_system(exchange_inputs.label("inputs").after("controls")),
+            );
+
+        // Added last, so every plugin's systems are already in these stages.
+        let mut sim = Schedule::default();
+        take_stage(app, &mut sim, CoreStage::Update);
+        take_stage(app, &mut sim, PhysicsStages::SyncBackend);
+        take_stage(app, &mut sim, PhysicsStages::SyncBackendFlush);
+        take_stage(app, &mut sim, PhysicsStages::StepSimulation);
+        take_stage(app, &mut sim, PhysicsStages::Writeback);
+        take_stage(app, &mut sim, PhysicsStages::DetectDespawn);
+        let collisions = app
+            .world
+            .resource::<Events<CollisionEvent>>()
+            .get_reader_current();
+        app.add_stage_after(
+            PhysicsStages::Writeback,
+            Rollback,
+            RollbackStage {
+                sim,
+                registry: registry(),
+                collisions,
+                pending: Vec::new(),
+            },
+        );
+    }
+}
+
+fn session_from_cli(cli: &Cli) -> Option<NetSession> {
+    let (transport, hosting) = if let Some(port) = cli.host {
+        (UdpTransport::host(port), true)
+    } else if let Some(addr) = &cli.join {
+        (UdpTransport::join(addr), false)
+    } else {
+        return None;
+    };
+    let transport = transport.expect("could not open UDP socket");
+
+    let transport: Box<dyn Transport> = if cli.net_latency > 0 || cli.net_loss > 0.0 {
+        Box::new(LinkShim::new(
+            transport,
+            Duration::from_millis(cli.net_latency),
+            cli.net_loss / 100.0,
+        ))
+    } else {
+        Box::new(transport)
+    };
+    Some(NetSession::new(transport, hosting))
+}
+
+/// Agrees the game seed, then starts a versus game on both peers.
+fn handshake(
+    mut session: ResMut<NetSession>,
+    mut game_seed: ResMut<GameSeed>,
+    mut mode: ResMut<PlayerMode>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    if !session.hosting && !session.connected {
+        session.send(Packet::Join);
+    }
+    while let Some(bytes) = session.transport.recv() {
+        match Packet::decode(&bytes) {
+            Some(Packet::Join) if session.hosting => {
+                let seed = game_seed.0.clone();
+                session.send(Packet::Hello { seed: seed.clone() });
+                session.seed = Some(seed);
+                session.connected = true;
+            }
+            Some(Packet::Hello { seed }) if !session.hosting => {
+                session.seed = Some(seed);
+                session.connected = true;
+            }
+            _ => (),
+        }
+    }
+    // Only the first round starts by itself; later ones follow the end screens.
+    if session.connected && session.round == 0 {
+        *game_seed = GameSeed(session.seed.clone().unwrap());
+        *mode = PlayerMode::Versus;
+        app_state.set(AppState::InGame).unwrap();
+    }
+}
+
+/// Rounds after the first would otherwise pick a different random seed on each peer.
+fn round_seed(mut session: ResMut<NetSession>, mut game_seed: ResMut<GameSeed>) {
+    let Some(seed) = session.seed.clone() else {
+        return;
+    };
+    *game_seed = match session.round {
+        0 => GameSeed(seed),
+        round => GameSeed(format!("{}-{}", seed, round)),
+    };
+    session.round += 1;
+}
+
+fn reset_frames(mut session: ResMut<NetSession>) {
+    session.frame = 0;
+    session.sim_frame = 0;
+    session.sim_time = None;
+    session.rollback = None;
+    session.held_end = None;
+    session.unconfirmed = 0;
+    session.local_inputs.clear();
+    session.remote_inputs.clear();
+    session.predicted.clear();
+    session.snapshots.clear();
+}
+
+fn mark_remote(
+    mut commands: Commands,
+    session: Res<NetSession>,
+    ships: Query<(Entity, &Pilot), (With<Playable>, Without<RemoteControlled>)>,
+) {
+    for (ship, pilot) in ships.iter() {
+        if pilot.0 != session.local {
+            commands.entity(ship).insert(RemoteControlled);
+        }
+    }
+}
+
+/// Gives each ship its input for the frame being simulated. On a new frame
+/// the keyboard input is scheduled and sent first.
+fn exchange_inputs(
+    mut session: ResMut<NetSession>,
+    mut ships: Query<(&Pilot, &mut ShipInput), With<Playable>>,
+) {
+    let frame = session.sim_frame;
+    if frame == session.frame {
+        let local = session.local;
+        if let Some((_, input)) = ships.iter().find(|(pilot, _)| pilot.0 == local) {
+            session.record_local(frame, input.to_bits());
+        }
+        session.send_inputs(frame);
+    }
+
+    let (local_bits, remote_bits) = session.inputs_at(frame);
+    for (pilot, mut input) in ships.iter_mut() {
+        *input = ShipInput::from_bits(if pilot.0 == session.local {
+            local_bits
+        } else {
+            remote_bits
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MAX_PLAYERS;
+    use std::thread::sleep;
+
+    const FRAMES: u32 = 120;
+
+    /// Inputs that change every frame, so most guesses are wrong.
+    fn pattern(peer: u32, frame: u32) -> u8 {
+        ((frame * 7 + peer * 13) % 63 + 1) as u8
+    }
+
+    fn pair(wrap: impl Fn(UdpTransport) -> Box<dyn Transport>) -> (NetSession, NetSession) {
+        let host = UdpTransport::host(0).unwrap();
+        let port = host.socket.local_addr().unwrap().port();
+        let join = UdpTransport::join(&format!("127.0.0.1:{}", port)).unwrap();
+        (
+            NetSession::new(wrap(host), true),
+            NetSession::new(wrap(join), false),
+        )
+    }
+
+    fn play(mut sessions: [&mut NetSession; 2]) {
+        for frame in 0..FRAMES {
+            for (peer, session) in sessions.iter_mut().enumerate() {
+                session.poll();
+                session.record_local(frame, pattern(peer as u32, frame));
+                session.send_inputs(frame);
+                session.inputs_at(frame);
+                session.frame = frame + 1;
+            }
+        }
+    }
+
+    /// Keeps resending the last packet until each peer has all the other's inputs.
+    fn settle(mut sessions: [&mut NetSession; 2]) -> bool {
+        let last = FRAMES + INPUT_DELAY;
+        let started = Instant::now();
+        while started.elapsed() < Duration::from_secs(5) {
+            for session in sessions.iter_mut() {
+                session.send_inputs(FRAMES - 1);
+                session.poll();
+            }
+            let complete =
+                |s: &&mut NetSession| (0..last).all(|f| s.remote_inputs.contains_key(&f));
+            if sessions.iter().all(complete) {
+                return true;
+            }
+            sleep(Duration::from_millis(5));
+        }
+        false
+    }
+
+    fn assert_confirmed(host: &NetSession, join: &NetSession) {
+        for frame in 0..FRAMES + INPUT_DELAY {
+            let sent = |s: &NetSession| s.local_inputs.get(&frame).copied().unwrap_or(0);
+            assert_eq!(host.remote_inputs[&frame], sent(join));
+            assert_eq!(join.remote_inputs[&frame], sent(host));
+        }
+        assert_eq!(host.unconfirmed, FRAMES + INPUT_DELAY);
+        assert_eq!(join.unconfirmed, FRAMES + INPUT_DELAY);
+    }
+
+    #[test]
+    fn loopback_confirms_every_input() {
+        let (mut host, mut join) = pair(|t| Box::new(t));
+        play([&mut host, &mut join]);
+        assert!(settle([&mut host, &mut join]));
+        assert_confirmed(&host, &join);
+    }
+
+    #[test]
+    fn lossy_link_rolls_back_and_recovers() {
+        let (mut host, mut join) =
+            pair(|t| Box::new(LinkShim::new(t, Duration::from_millis(50), 0.2)));
+        play([&mut host, &mut join]);
+        // Nothing arrives within the first frames, so the guesses go wrong as
+        // soon as the other peer's recorded inputs start.
+        assert!(settle([&mut host, &mut join]));
+        assert_eq!(host.rollback, Some(INPUT_DELAY));
+        assert_eq!(join.rollback, Some(INPUT_DELAY));
+        assert_confirmed(&host, &join);
+    }
+
+    #[test]
+    fn snapshot_restores_the_world() {
+        let registry = registry();
+        let mut world = World::new();
+        world.insert_resource(Score([3; MAX_PLAYERS]));
+        let ship = world
+            .spawn((
+                Transform::from_xyz(1.0, 2.0, 0.0),
+                RigidBody::Dynamic,
+                Cargo(vec![GlodTier::default()]),
+            ))
+            .id();
+        let torpedo = world
+            .spawn((Transform::default(), RigidBody::Dynamic, Weapon))
+            .id();
+        let snapshot = Snapshot::save(&mut world, &registry, 0, Vec::new());
+
+        world.get_mut::<Transform>(ship).unwrap().translation.x = 5.0;
+        world.entity_mut(ship).remove::<Cargo>();
+        world.despawn(torpedo);
+        let newer = world.spawn((Transform::default(), RigidBody::Dynamic)).id();
+        world.resource_mut::<Score>().0[0] = 9;
+
+        snapshot.restore(&mut world, &registry);
+        assert_eq!(world.get::<Transform>(ship).unwrap().translation.x, 1.0);
+        assert_eq!(world.get::<Cargo>(ship).unwrap().0.len(), 1);
+        assert!(world.get::<Weapon>(torpedo).is_some());
+        assert!(world.get_entity(newer).is_none());
+        assert_eq!(world.resource::<Score>().0[0], 3);
+    }
+}
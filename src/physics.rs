@@ -1,10 +1,40 @@
+use crate::Cli;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
+use bevy_rapier2d::geometry::Group;
 use std::f32::consts::PI;
+use std::marker::PhantomData;
 use std::ops::{Mul, Neg};
+use std::time::Duration;
 extern crate web_sys;
 
 pub const PIXELS_PER_METER: f32 = 1.0;
+/// Physics step used when runs must be reproducible, such as replays and online play.
+pub const FRAME_DT: f32 = 1.0 / 60.0;
+
+/// Time for gameplay timers, which under a fixed timestep follows the physics.
+#[derive(SystemParam)]
+pub struct GameTime<'w, 's> {
+    cli: Res<'w, Cli>,
+    time: Res<'w, Time>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl GameTime<'_, '_> {
+    pub fn delta(&self) -> Duration {
+        if self.cli.needs_fixed_timestep() {
+            Duration::from_secs_f32(FRAME_DT)
+        } else {
+            self.time.delta()
+        }
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta().as_secs_f32()
+    }
+}
 
 #[repr(u32)]
 pub enum CollisionMemberships {
@@ -14,6 +44,10 @@ pub enum CollisionMemberships {
     InertWeapon = 0b1000,
     KineticWeapon = 0b10000,
     Glod = 0b100000,
+    Rival = 0b1000000,
+    EnemyBase = 0b10000000,
+    EnemyOrdnance = 0b100000000,
+    Debris = 0b1000000000,
 }
 
 #[repr(u32)]
@@ -21,14 +55,54 @@ pub enum CollisionFilters {
     Friend = CollisionMemberships::FriendlyBase as u32
         | CollisionMemberships::Glod as u32
         | CollisionMemberships::Enemy as u32
+        | CollisionMemberships::KineticWeapon as u32
+        | CollisionMemberships::EnemyOrdnance as u32
+        | CollisionMemberships::Debris as u32
+        | CollisionMemberships::Rival as u32,
+    Rival = CollisionMemberships::FriendlyBase as u32
+        | CollisionMemberships::Glod as u32
+        | CollisionMemberships::Enemy as u32
+        | CollisionMemberships::KineticWeapon as u32
+        | CollisionMemberships::EnemyOrdnance as u32
+        | CollisionMemberships::Debris as u32
+        | CollisionMemberships::Friend as u32,
+    Enemy = CollisionMemberships::Friend as u32
+        | CollisionMemberships::Rival as u32
         | CollisionMemberships::KineticWeapon as u32,
-    Enemy = CollisionMemberships::Friend as u32 | CollisionMemberships::KineticWeapon as u32,
     InertWeapon = CollisionMemberships::KineticWeapon as u32,
     KineticWeapon = CollisionMemberships::Friend as u32
+        | CollisionMemberships::Rival as u32
         | CollisionMemberships::InertWeapon as u32
-        | CollisionMemberships::Enemy as u32,
+        | CollisionMemberships::Enemy as u32
+        | CollisionMemberships::EnemyBase as u32
+        | CollisionMemberships::EnemyOrdnance as u32
+        | CollisionMemberships::Debris as u32,
+    EnemyBase = CollisionMemberships::KineticWeapon as u32,
+    // Enemy torpedoes pass through other enemies.
+    EnemyOrdnance = CollisionMemberships::Friend as u32
+        | CollisionMemberships::Rival as u32
+        | CollisionMemberships::KineticWeapon as u32
+        | CollisionMemberships::Debris as u32,
+    // The enemy is a sensor, so asteroids push it about by hand instead.
+    Debris = CollisionMemberships::Friend as u32
+        | CollisionMemberships::Rival as u32
+        | CollisionMemberships::KineticWeapon as u32
+        | CollisionMemberships::EnemyOrdnance as u32
+        | CollisionMemberships::Debris as u32,
     // FriendlyBase, Glod
-    WithFriend = CollisionMemberships::Friend as u32,
+    WithFriend = CollisionMemberships::Friend as u32 | CollisionMemberships::Rival as u32,
+}
+
+/// Collision groups for a ship, so that ships on opposing teams collide.
+pub fn ship_collision_groups(team: usize) -> CollisionGroups {
+    let (membership, filter) = match team {
+        0 => (CollisionMemberships::Friend, CollisionFilters::Friend),
+        _ => (CollisionMemberships::Rival, CollisionFilters::Rival),
+    };
+    CollisionGroups::new(
+        Group::from_bits(membership as u32).unwrap(),
+        Group::from_bits(filter as u32).unwrap(),
+    )
 }
 
 pub enum TorqueDirection {
@@ -139,4 +213,9 @@ impl BodyForce {
         let vec = trans.local_x();
         Vec2::new(self.forward * vec.x, self.forward * vec.y)
     }
+
+    pub fn apply(&self, trans: &Transform, ext_force: &mut ExternalForce) {
+        ext_force.torque = self.torque;
+        ext_force.force = self.force_from_transform(trans);
+    }
 }
@@ -0,0 +1,168 @@
+//! Power-ups lying about the arena, picked up by flying over them.
+use crate::{spawn_torpedo_stock, CollisionFilters, CollisionMemberships};
+use crate::{
+    Explodee, GameState, GameTime, GlodPoints, Playable, PlayableCollider, Upgrades,
+    WeaponPreLaunch,
+};
+use bevy::prelude::*;
+use bevy_rapier2d::geometry::Group;
+use bevy_rapier2d::prelude::*;
+use std::time::Duration;
+
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Game).with_system(spawn_pickups))
+            .add_system_set(
+                SystemSet::on_update(GameState::Game)
+                    .with_system(collect_pickups)
+                    .with_system(wear_off_effects),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Victory).with_system(despawn_pickups))
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(despawn_pickups));
+    }
+}
+
+pub const PICKUP_QUANTITY: usize = 3;
+pub const PICKUP_RADIUS: f32 = 5.0;
+pub const BOOST_DURATION: Duration = Duration::from_secs(5);
+pub const BOOST_FACTOR: f32 = 1.6;
+pub const SHIELD_DURATION: Duration = Duration::from_secs(8);
+
+#[derive(Component, Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Pickup {
+    SpeedBoost,
+    Shield,
+    TorpedoRefill,
+}
+
+pub const PICKUPS: [Pickup; 3] = [Pickup::SpeedBoost, Pickup::Shield, Pickup::TorpedoRefill];
+
+impl Pickup {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pickup::SpeedBoost => "boost",
+            Pickup::Shield => "shield",
+            Pickup::TorpedoRefill => "torpedoes",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Pickup> {
+        PICKUPS
+            .into_iter()
+            .find(|pickup| pickup.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Extra thrust until the timer runs out.
+#[derive(Component, Clone)]
+pub struct SpeedBoost(pub Timer);
+
+/// Enemy contact is harmless until the timer runs out.
+#[derive(Component, Clone)]
+pub struct Shielded(pub Timer);
+
+fn spawn_pickups(mut commands: Commands, glods: Res<GlodPoints>) {
+    for (pos, pickup) in glods.pickups.iter() {
+        commands.spawn((
+            RigidBody::Dynamic,
+            Collider::ball(PICKUP_RADIUS),
+            CollisionGroups::new(
+                Group::from_bits(CollisionMemberships::Glod as u32).unwrap(),
+                Group::from_bits(CollisionFilters::WithFriend as u32).unwrap(),
+            ),
+            Damping {
+                linear_damping: 2.0,
+                angular_damping: 0.0,
+            },
+            Sensor,
+            *pickup,
+            Explodee,
+            ExternalForce {
+                force: Vec2::ZERO,
+                torque: 0.0,
+            },
+            ExternalImpulse {
+                impulse: Vec2::ZERO,
+                torque_impulse: 0.0,
+            },
+            Velocity::zero(),
+            Transform::from_translation(*pos),
+        ));
+    }
+}
+
+fn despawn_pickups(mut commands: Commands, pickups: Query<Entity, With<Pickup>>) {
+    for pickup in pickups.iter() {
+        commands.entity(pickup).despawn();
+    }
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    upgrades: Res<Upgrades>,
+    colliders: Query<&Parent, With<PlayableCollider>>,
+    ships: Query<Entity, With<Playable>>,
+    pickups: Query<&Pickup>,
+    stock: Query<(Entity, &Parent), With<WeaponPreLaunch>>,
+    mut collision_events: EventReader<CollisionEvent>,
+) {
+    for collision_event in collision_events.iter() {
+        let CollisionEvent::Started(h1, h2, _) = collision_event else {
+            continue;
+        };
+        let (collider, other) = if colliders.contains(*h1) {
+            (h1, h2)
+        } else {
+            (h2, h1)
+        };
+        let (Ok(ship), Ok(pickup)) = (colliders.get(*collider), pickups.get(*other)) else {
+            continue;
+        };
+        let Ok(ship) = ships.get(ship.get()) else {
+            continue;
+        };
+        commands.entity(*other).despawn();
+        match pickup {
+            Pickup::SpeedBoost => {
+                commands
+                    .entity(ship)
+                    .insert(SpeedBoost(Timer::new(BOOST_DURATION, TimerMode::Once)));
+            }
+            Pickup::Shield => {
+                commands
+                    .entity(ship)
+                    .insert(Shielded(Timer::new(SHIELD_DURATION, TimerMode::Once)));
+            }
+            Pickup::TorpedoRefill => {
+                for (torpedo, parent) in stock.iter() {
+                    if parent.get() == ship {
+                        commands.entity(torpedo).despawn();
+                    }
+                }
+                commands
+                    .entity(ship)
+                    .with_children(|children| spawn_torpedo_stock(children, &upgrades));
+            }
+        }
+    }
+}
+
+fn wear_off_effects(
+    time: GameTime,
+    mut commands: Commands,
+    mut boosted: Query<(Entity, &mut SpeedBoost)>,
+    mut shielded: Query<(Entity, &mut Shielded)>,
+) {
+    for (ship, mut boost) in boosted.iter_mut() {
+        if boost.0.tick(time.delta()).finished() {
+            commands.entity(ship).remove::<SpeedBoost>();
+        }
+    }
+    for (ship, mut shield) in shielded.iter_mut() {
+        if shield.0.tick(time.delta()).finished() {
+            commands.entity(ship).remove::<Shielded>();
+        }
+    }
+}
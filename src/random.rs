@@ -1,16 +1,33 @@
-use crate::{AppState, GameState};
-use crate::{FoeStartingPoint, FriendStartingPoint, START_RADIUS};
-use crate::{GLOD_QUANTITY, GLOD_RADIUS};
+use crate::{AppState, GameState, Level, PlayerMode, Tutorial};
+use crate::{FoeStartingPoint, FriendStartingPoint, RivalStartingPoint, START_RADIUS};
+use crate::{GlodPattern, GlodTier, GLOD_PATTERNS, GLOD_QUANTITY, GLOD_RADIUS};
+use crate::{Pickup, HEAVY_GLOD_CHANCE, PICKUPS, PICKUP_QUANTITY, RARE_GLOD_CHANCE};
 use bevy::prelude::*;
 use rand::distributions::{Alphanumeric, DistString};
 use rand::prelude::*;
 use rand_pcg::Pcg64;
 use rand_seeder::Seeder;
+use std::ops::Range;
 
 #[derive(Hash, PartialEq, Eq, Clone, Debug)]
 #[derive(Resource)]
 pub struct GameSeed(pub String);
 
+/// Longest seed that can be typed in.
+pub const SEED_MAX_LEN: usize = 32;
+
+impl GameSeed {
+    /// Characters a typed seed may contain; the same as generated and daily seeds use.
+    pub fn allows(c: char) -> bool {
+        c.is_ascii_alphanumeric() || c == '-' || c == '_'
+    }
+
+    /// Whether `seed` is one that could have been typed in.
+    pub fn is_valid(seed: &str) -> bool {
+        !seed.is_empty() && seed.chars().all(GameSeed::allows)
+    }
+}
+
 impl Default for GameSeed {
     fn default() -> GameSeed {
         GameSeed(Alphanumeric.sample_string(&mut rand::thread_rng(), 8))
@@ -19,25 +36,88 @@ impl Default for GameSeed {
 
 #[derive(Resource)]
 pub struct GlodPoints {
-    pub glods: [Vec3; GLOD_QUANTITY],
+    pub glods: Vec<Vec3>,
+    /// Starting velocity of each glod, zero unless the pattern drifts.
+    pub drift: Vec<Vec2>,
+    pub tiers: Vec<GlodTier>,
+    pub pickups: Vec<(Vec3, Pickup)>,
 }
 
-struct GlodCoord {
-    rng: Pcg64,
+impl GlodPoints {
+    /// Points to be had from all the glod on the map.
+    pub fn total_value(&self) -> u32 {
+        self.tiers.iter().map(|tier| tier.value() as u32).sum()
+    }
+}
+
+/// Limits on where the generator may place bases and glod.
+#[derive(Resource, Clone, Debug)]
+pub struct MapConstraints {
+    /// Smallest distance between the centres of any two bases.
+    pub min_base_separation: f32,
+    /// Glod is kept at least this far from the centre of every base.
+    pub base_exclusion: f32,
+    /// Smallest distance between two glod, relaxed if the map fills up.
+    pub glod_spacing: f32,
+    /// Glod is kept at least this far from the arena edge.
+    pub edge_margin: f32,
+    /// Rejected candidates before relaxing the base separation or the glod spacing.
+    pub max_attempts: u32,
 }
 
-impl Iterator for GlodCoord {
-    type Item = Vec3;
+/// Candidates drawn for a base, the glod or the pickups before settling for
+/// the best base or however many fit.
+pub const MAX_DRAWS: u32 = 10_000;
+
+impl Default for MapConstraints {
+    fn default() -> MapConstraints {
+        MapConstraints {
+            min_base_separation: 300.0,
+            base_exclusion: START_RADIUS + 20.0,
+            glod_spacing: 30.0,
+            edge_margin: 20.0,
+            max_attempts: 30,
+        }
+    }
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        Some(Vec3::new(
-            self.rng
-                .gen_range(-320.0 + GLOD_RADIUS..320.0 - GLOD_RADIUS),
-            self.rng
-                .gen_range(-320.0 + GLOD_RADIUS..320.0 - GLOD_RADIUS),
-            0.0,
-        ))
+/// Draws a base in the given quadrant, clear of `others` by the minimum
+/// separation. Like the glod spacing, the separation is relaxed after every
+/// `max_attempts` misses; if the draws still run out, the clearest candidate
+/// is taken.
+fn place_base(
+    rng: &mut Pcg64,
+    x: Range<f32>,
+    y: Range<f32>,
+    others: &[Vec3],
+    constraints: &MapConstraints,
+) -> Vec3 {
+    let clearance = |pos: Vec3| {
+        others
+            .iter()
+            .map(|other| other.distance(pos))
+            .fold(f32::INFINITY, f32::min)
+    };
+    let mut separation = constraints.min_base_separation;
+    let mut misses = 0;
+    let mut best = Vec3::ZERO;
+    let mut best_clearance = f32::NEG_INFINITY;
+    for _ in 0..MAX_DRAWS {
+        let pos = Vec3::new(rng.gen_range(x.clone()), rng.gen_range(y.clone()), 0.0);
+        if clearance(pos) >= separation {
+            return pos;
+        }
+        if clearance(pos) > best_clearance {
+            best = pos;
+            best_clearance = clearance(pos);
+        }
+        misses += 1;
+        if misses >= constraints.max_attempts {
+            separation *= 0.9;
+            misses = 0;
+        }
     }
+    best
 }
 
 pub struct RandPlugin;
@@ -46,46 +126,267 @@ impl Plugin for RandPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(GameSeed::default())
             .insert_resource(GlodPoints {
-                glods: [Vec3::ZERO; GLOD_QUANTITY],
+                glods: Vec::new(),
+                drift: Vec::new(),
+                tiers: Vec::new(),
+                pickups: Vec::new(),
             })
+            .insert_resource(MapConstraints::default())
             .insert_resource(FriendStartingPoint(Vec3::ZERO))
+            .insert_resource(RivalStartingPoint(Vec3::ZERO))
             .insert_resource(FoeStartingPoint(Vec3::ZERO))
             .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(gen_random))
             .add_system_set(
-                SystemSet::on_enter(GameState::SampleRandom).with_system(update_game_resources),
+                SystemSet::on_enter(GameState::SampleRandom)
+                    .with_system(update_game_resources.label("game_resources")),
             );
     }
 }
 
+/// Base and glod positions for a map, before anything is spawned.
+#[derive(Clone, Debug)]
+pub struct MapLayout {
+    pub friend: Vec3,
+    pub foe: Vec3,
+    /// Only placed when the player mode has a rival team.
+    pub rival: Option<Vec3>,
+    pub pattern: GlodPattern,
+    pub glods: Vec<Vec3>,
+    pub drift: Vec<Vec2>,
+    pub tiers: Vec<GlodTier>,
+    pub pickups: Vec<(Vec3, Pickup)>,
+}
+
+/// Picks the tier of a seeded glod, most of which are common.
+fn draw_tier(rng: &mut Pcg64) -> GlodTier {
+    let roll: f64 = rng.gen();
+    if roll < RARE_GLOD_CHANCE {
+        GlodTier::Rare
+    } else if roll < RARE_GLOD_CHANCE + HEAVY_GLOD_CHANCE {
+        GlodTier::Heavy
+    } else {
+        GlodTier::Common
+    }
+}
+
+impl MapLayout {
+    /// Generates the map for a seed, with the glod pattern the seed picks
+    /// unless `pattern` overrides it. Rejected candidates are redrawn from
+    /// the same random stream, so a seed always gives the same map. Overly
+    /// tight constraints give fewer glod rather than no map at all.
+    pub fn generate(
+        seed: &GameSeed,
+        constraints: &MapConstraints,
+        pattern: Option<GlodPattern>,
+        mode: PlayerMode,
+    ) -> MapLayout {
+        let mut rng: Pcg64 = Seeder::from(seed.0.clone()).make_rng();
+        let low = -320.0 + START_RADIUS..0.0 - START_RADIUS;
+        let high = 0.0 + START_RADIUS..320.0 - START_RADIUS;
+        let friend = place_base(&mut rng, low.clone(), low.clone(), &[], constraints);
+        let foe = place_base(&mut rng, high.clone(), high.clone(), &[friend], constraints);
+        // Always drawn, so that a seed lays out the glod the same in every mode.
+        let rival = place_base(&mut rng, high, low, &[friend, foe], constraints);
+        let rival = (mode.teams() > 1).then_some(rival);
+
+        // Always drawn, so that overriding the pattern leaves the rest of the stream alone.
+        let drawn = GLOD_PATTERNS[rng.gen_range(0..GLOD_PATTERNS.len())];
+        let pattern = pattern.unwrap_or(drawn);
+        let edge = 320.0 - f32::max(GLOD_RADIUS, constraints.edge_margin);
+        let mut distribution = pattern.distribution(&mut rng, foe, edge);
+
+        let bases: Vec<Vec3> = [friend, foe].into_iter().chain(rival).collect();
+        let mut spacing = constraints.glod_spacing;
+        let mut misses = 0;
+        let mut glods: Vec<Vec3> = Vec::with_capacity(GLOD_QUANTITY);
+        let mut drift: Vec<Vec2> = Vec::with_capacity(GLOD_QUANTITY);
+        for _ in 0..MAX_DRAWS {
+            if glods.len() == GLOD_QUANTITY {
+                break;
+            }
+            let candidate = distribution.sample(&mut rng);
+            let inside = candidate.x.abs() <= edge && candidate.y.abs() <= edge;
+            let clear_of_bases = bases
+                .iter()
+                .all(|base| base.distance(candidate) >= constraints.base_exclusion);
+            let spaced = glods.iter().all(|glod| glod.distance(candidate) >= spacing);
+            if inside && clear_of_bases && spaced {
+                glods.push(candidate);
+                drift.push(distribution.drift(&mut rng, candidate));
+                misses = 0;
+            } else {
+                misses += 1;
+                if misses >= constraints.max_attempts {
+                    spacing *= 0.9;
+                    misses = 0;
+                }
+            }
+        }
+
+        // Drawn after the positions, so tiers and pickups leave the layout alone.
+        let tiers = (0..glods.len()).map(|_| draw_tier(&mut rng)).collect();
+        let mut pickups = Vec::with_capacity(PICKUP_QUANTITY);
+        for _ in 0..MAX_DRAWS {
+            if pickups.len() == PICKUP_QUANTITY {
+                break;
+            }
+            let pos = Vec3::new(rng.gen_range(-edge..edge), rng.gen_range(-edge..edge), 0.0);
+            let kind = PICKUPS[rng.gen_range(0..PICKUPS.len())];
+            if bases
+                .iter()
+                .all(|base| base.distance(pos) >= constraints.base_exclusion)
+            {
+                pickups.push((pos, kind));
+            }
+        }
+
+        MapLayout {
+            friend,
+            foe,
+            rival,
+            pattern,
+            glods,
+            drift,
+            tiers,
+            pickups,
+        }
+    }
+
+    /// Replaces whatever the level places by hand.
+    pub fn with_level(mut self, level: &Level) -> MapLayout {
+        self.friend = level.friend.unwrap_or(self.friend);
+        self.foe = level.foe.unwrap_or(self.foe);
+        if self.rival.is_some() {
+            self.rival = level.rival.or(self.rival);
+        }
+        if !level.glods.is_empty() {
+            self.glods = level.glods.clone();
+            self.drift = vec![Vec2::ZERO; self.glods.len()];
+            self.tiers = level.tiers.clone();
+        }
+        if !level.pickups.is_empty() {
+            self.pickups = level.pickups.clone();
+        }
+        self
+    }
+}
+
 fn update_game_resources(
     game_seed: Res<GameSeed>,
     mut friend: ResMut<FriendStartingPoint>,
     mut foe: ResMut<FoeStartingPoint>,
+    mut rival: ResMut<RivalStartingPoint>,
     mut glod_points: ResMut<GlodPoints>,
+    constraints: Res<MapConstraints>,
+    mode: Res<PlayerMode>,
+    level: Option<Res<Level>>,
+    tutorial: Res<Tutorial>,
 ) {
-    let mut rng: Pcg64 = Seeder::from((*game_seed).0.clone()).make_rng();
-    *friend = FriendStartingPoint(Vec3::new(
-        rng.gen_range(-320.0 + START_RADIUS..0.0 - START_RADIUS),
-        rng.gen_range(-320.0 + START_RADIUS..0.0 - START_RADIUS),
-        0.0,
-    ));
-    *foe = FoeStartingPoint(Vec3::new(
-        rng.gen_range(0.0 + START_RADIUS..320.0 - START_RADIUS),
-        rng.gen_range(0.0 + START_RADIUS..320.0 - START_RADIUS),
-        0.0,
-    ));
-
-    let g = GlodCoord { rng };
+    let level = if tutorial.active() {
+        Some(Tutorial::level())
+    } else {
+        level.map(|level| level.clone())
+    };
+    let pattern = level.as_ref().and_then(|level| level.pattern);
+    let mut layout = MapLayout::generate(&game_seed, &constraints, pattern, *mode);
+    if let Some(level) = level {
+        layout = layout.with_level(&level);
+    }
+    *friend = FriendStartingPoint(layout.friend);
+    *foe = FoeStartingPoint(layout.foe);
+    *rival = RivalStartingPoint(layout.rival.unwrap_or_default());
     *glod_points = GlodPoints {
-        glods: g
-            .into_iter()
-            .take(GLOD_QUANTITY)
-            .collect::<Vec<Vec3>>()
-            .try_into()
-            .unwrap(),
+        glods: layout.glods,
+        drift: layout.drift,
+        tiers: layout.tiers,
+        pickups: layout.pickups,
     };
 }
 
 fn gen_random(mut game_state: ResMut<State<GameState>>) {
     game_state.set(GameState::SampleRandom).unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seeds() -> impl Iterator<Item = GameSeed> {
+        (0..40).map(|i| GameSeed(format!("test-{}", i)))
+    }
+
+    #[test]
+    fn generate_fills_the_map() {
+        let constraints = MapConstraints::default();
+        let edge = 320.0 - f32::max(GLOD_RADIUS, constraints.edge_margin);
+        for seed in seeds() {
+            for pattern in GLOD_PATTERNS {
+                let layout =
+                    MapLayout::generate(&seed, &constraints, Some(pattern), PlayerMode::Versus);
+                assert_eq!(layout.glods.len(), GLOD_QUANTITY, "{:?} {:?}", seed, pattern);
+                assert_eq!(layout.tiers.len(), layout.glods.len());
+                assert_eq!(layout.pickups.len(), PICKUP_QUANTITY);
+                for glod in &layout.glods {
+                    assert!(glod.x.abs() <= edge && glod.y.abs() <= edge);
+                    for base in [layout.friend, layout.foe].into_iter().chain(layout.rival) {
+                        assert!(base.distance(*glod) >= constraints.base_exclusion);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generate_gives_up_on_impossible_constraints() {
+        let constraints = MapConstraints {
+            base_exclusion: 2000.0,
+            max_attempts: 0,
+            ..default()
+        };
+        for seed in seeds().take(5) {
+            for pattern in GLOD_PATTERNS {
+                let layout =
+                    MapLayout::generate(&seed, &constraints, Some(pattern), PlayerMode::Solo);
+                assert!(layout.glods.is_empty());
+                assert!(layout.pickups.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn generate_is_deterministic() {
+        let constraints = MapConstraints::default();
+        let seed = GameSeed("same".to_string());
+        let first = MapLayout::generate(&seed, &constraints, None, PlayerMode::Solo);
+        let second = MapLayout::generate(&seed, &constraints, None, PlayerMode::Solo);
+        assert_eq!(first.glods, second.glods);
+        assert_eq!(first.pickups, second.pickups);
+    }
+
+    #[test]
+    fn generate_only_places_a_rival_base_for_a_rival_team() {
+        let constraints = MapConstraints::default();
+        for seed in seeds().take(5) {
+            let solo = MapLayout::generate(&seed, &constraints, None, PlayerMode::Solo);
+            let versus = MapLayout::generate(&seed, &constraints, None, PlayerMode::Versus);
+            assert!(solo.rival.is_none());
+            assert!(versus.rival.is_some());
+            assert_eq!((solo.friend, solo.foe), (versus.friend, versus.foe));
+        }
+    }
+
+    #[test]
+    fn generate_relaxes_an_impossible_base_separation() {
+        let constraints = MapConstraints {
+            min_base_separation: 5000.0,
+            ..default()
+        };
+        for seed in seeds().take(5) {
+            let layout = MapLayout::generate(&seed, &constraints, None, PlayerMode::Versus);
+            let rival = layout.rival.unwrap();
+            assert!(layout.friend.x < 0.0 && layout.friend.y < 0.0);
+            assert!(layout.foe.x > 0.0 && layout.foe.y > 0.0);
+            assert!(rival.x > 0.0 && rival.y < 0.0);
+        }
+    }
+}
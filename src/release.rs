@@ -0,0 +1,155 @@
+//! When the enemy wakes up, and the enemy base it returns to.
+use crate::{
+    EnemyBase, EnemyState, GameState, GameTime, GlodPoints, IsBase, IsEnemy, IsGlod, Playable,
+    PlayableCollider, ReleaseRule, ReleaseTrigger, RoundClock, ShipInput, Team,
+};
+use bevy::ecs::schedule::StateError;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+pub struct ReleasePlugin;
+
+impl Plugin for ReleasePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<EnemyReturned>().add_system_set(
+            SystemSet::on_update(GameState::Game)
+                .with_system(release_on_leaving_base.label("release"))
+                .with_system(release_on_timer.label("release"))
+                .with_system(release_on_glod.label("release"))
+                .with_system(release_on_noise.label("release").after("inputs"))
+                .with_system(wake_stages.after("release"))
+                .with_system(respawn_enemies.label("respawn_enemies")),
+        );
+    }
+}
+
+/// Enemies beyond this distance from the centre are sent back to the enemy base.
+const ENEMY_EDGE: f32 = 342.0;
+
+/// An enemy sent back to the enemy base after leaving the arena.
+pub struct EnemyReturned(pub Entity);
+
+/// An enemy still waiting at the enemy base after the others are released.
+#[derive(Component, Clone)]
+pub struct Dormant(pub Timer);
+
+/// Wakes the enemy. Several triggers can fire on the same frame, so a
+/// release already on its way is left to happen.
+pub fn release(enemy_state: &mut State<EnemyState>) {
+    if *enemy_state.current() == EnemyState::PreStart {
+        match enemy_state.set(EnemyState::Released) {
+            Ok(()) | Err(StateError::StateAlreadyQueued) => (),
+            Err(err) => panic!("could not release the enemy: {:?}", err),
+        }
+    }
+}
+
+fn release_on_leaving_base(
+    rule: Res<ReleaseRule>,
+    colliders: Query<&Parent, With<PlayableCollider>>,
+    ships: Query<&Team, With<Playable>>,
+    bases: Query<&Team, With<IsBase>>,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut enemy_state: ResMut<State<EnemyState>>,
+) {
+    for collision_event in collision_events.iter() {
+        let CollisionEvent::Stopped(h1, h2, _) = collision_event else {
+            continue;
+        };
+        if rule.trigger != ReleaseTrigger::LeaveBase {
+            continue;
+        }
+        let (collider, other) = if colliders.contains(*h1) {
+            (h1, h2)
+        } else {
+            (h2, h1)
+        };
+        let (Ok(ship), Ok(base)) = (colliders.get(*collider), bases.get(*other)) else {
+            continue;
+        };
+        if ships.get(ship.get()) == Ok(base) {
+            release(&mut enemy_state);
+            return;
+        }
+    }
+}
+
+fn release_on_timer(
+    rule: Res<ReleaseRule>,
+    clock: Res<RoundClock>,
+    mut enemy_state: ResMut<State<EnemyState>>,
+) {
+    if rule.trigger == ReleaseTrigger::Timer && clock.seconds() >= rule.delay_seconds {
+        release(&mut enemy_state);
+    }
+}
+
+/// Counts glod taken off the field, so dropped glod has to be picked up again.
+fn release_on_glod(
+    rule: Res<ReleaseRule>,
+    glod_points: Res<GlodPoints>,
+    glods: Query<(), With<IsGlod>>,
+    mut enemy_state: ResMut<State<EnemyState>>,
+) {
+    if rule.trigger != ReleaseTrigger::Glod {
+        return;
+    }
+    let collected = glod_points.glods.len().saturating_sub(glods.iter().count());
+    if collected >= rule.glod {
+        release(&mut enemy_state);
+    }
+}
+
+fn release_on_noise(
+    rule: Res<ReleaseRule>,
+    ships: Query<(&Transform, &ShipInput), With<Playable>>,
+    listeners: Query<&Transform, Or<(With<IsEnemy>, With<EnemyBase>)>>,
+    mut enemy_state: ResMut<State<EnemyState>>,
+) {
+    if rule.trigger != ReleaseTrigger::Noise {
+        return;
+    }
+    let heard = ships.iter().any(|(ship, input)| {
+        (input.thrust || input.fire)
+            && listeners.iter().any(|listener| {
+                listener.translation.distance(ship.translation) <= rule.noise_radius
+            })
+    });
+    if heard {
+        release(&mut enemy_state);
+    }
+}
+
+fn wake_stages(
+    time: GameTime,
+    enemy_state: Res<State<EnemyState>>,
+    mut commands: Commands,
+    mut dormant: Query<(Entity, &mut Dormant)>,
+) {
+    if *enemy_state.current() != EnemyState::Released {
+        return;
+    }
+    for (enemy, mut timer) in dormant.iter_mut() {
+        if timer.0.tick(time.delta()).finished() {
+            commands.entity(enemy).remove::<Dormant>();
+        }
+    }
+}
+
+fn respawn_enemies(
+    base: Query<&Transform, (With<EnemyBase>, Without<IsEnemy>)>,
+    mut enemies: Query<(Entity, &mut Transform, &mut Velocity), With<IsEnemy>>,
+    mut returned: EventWriter<EnemyReturned>,
+) {
+    let Ok(base) = base.get_single() else {
+        return;
+    };
+    for (enemy, mut trans, mut velocity) in enemies.iter_mut() {
+        let pos = trans.translation;
+        if pos.x.abs() > ENEMY_EDGE || pos.y.abs() > ENEMY_EDGE {
+            trans.translation = base.translation;
+            *velocity = Velocity::zero();
+            returned.send(EnemyReturned(enemy));
+        }
+    }
+}
@@ -0,0 +1,500 @@
+//! Recording and playing back the inputs of a game.
+//!
+//! A replay file starts with `seed`, `difficulty`, `mode`, `game-mode` and
+//! `upgrades` lines, then one line per rule giving its values in order. A
+//! `level <n>` line follows, with the `n` lines of the hand-made level the
+//! game was played on, if any. The rest is one line per frame holding each
+//! pilot's `ShipInput` bits separated by commas. Playback uses the recorded
+//! upgrades and level in place of the player's own.
+use crate::{
+    exit_with, AsteroidRule, BaseConfig, Cli, DepositRule, Difficulty, DirectorRule, EnemyFireRule,
+    FogRule, GameMode, GameSeed, GameState, HazardRule, Level, Pilot, Playable, PlayerMode,
+    ReleaseRule, ReleaseTrigger, RuleSet, Rules, ShipInput, SiegeRule, Upgrades,
+};
+use bevy::prelude::*;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Resource, Clone, Debug)]
+pub struct Replay {
+    pub seed: GameSeed,
+    pub difficulty: Difficulty,
+    pub mode: PlayerMode,
+    pub game_mode: GameMode,
+    pub upgrades: Upgrades,
+    pub rules: RuleSet,
+    pub level: Option<Level>,
+    pub frames: Vec<Vec<u8>>,
+}
+
+/// The values on a rule's header line, read in order.
+struct Fields {
+    key: &'static str,
+    values: std::vec::IntoIter<String>,
+}
+
+impl Fields {
+    fn new(key: &'static str, line: &str) -> Fields {
+        let values: Vec<String> = line.split_whitespace().map(str::to_string).collect();
+        Fields {
+            key,
+            values: values.into_iter(),
+        }
+    }
+
+    fn next<T: FromStr>(&mut self) -> Result<T, String> {
+        self.values
+            .next()
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| format!("bad {} line in replay", self.key))
+    }
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+        Replay::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Replay, String> {
+        let mut lines = text.lines();
+        let mut header = |key: &str| {
+            lines
+                .next()
+                .and_then(|line| line.strip_prefix(key))
+                .and_then(|rest| rest.strip_prefix(' '))
+                .map(str::to_string)
+                .ok_or_else(|| format!("replay is missing its {} line", key))
+        };
+        let seed = header("seed")?;
+        if !GameSeed::is_valid(&seed) {
+            return Err(format!("invalid seed in replay: {}", seed));
+        }
+        let seed = GameSeed(seed);
+        let difficulty = header("difficulty")?;
+        let difficulty = Difficulty::from_name(&difficulty)
+            .ok_or_else(|| format!("unknown difficulty: {}", difficulty))?;
+        let mode = header("mode")?;
+        let mode = PlayerMode::from_name(&mode).ok_or_else(|| format!("unknown mode: {}", mode))?;
+        let game_mode = header("game-mode")?;
+        let game_mode = GameMode::from_name(&game_mode)
+            .ok_or_else(|| format!("unknown game mode: {}", game_mode))?;
+        let upgrades = Upgrades::from_storage(&header("upgrades")?);
+
+        let mut rules = RuleSet::default();
+        let mut deposit = Fields::new("deposit", &header("deposit")?);
+        rules.deposit = DepositRule {
+            enabled: deposit.next()?,
+            victory_minimum: deposit.next()?,
+        };
+        let mut release = Fields::new("release", &header("release")?);
+        rules.release = ReleaseRule {
+            trigger: ReleaseTrigger::from_key(&release.next::<String>()?)
+                .ok_or_else(|| "bad release line in replay".to_string())?,
+            delay_seconds: release.next()?,
+            glod: release.next()?,
+            noise_radius: release.next()?,
+            stages: release.next()?,
+            stage_seconds: release.next()?,
+        };
+        let mut siege = Fields::new("siege", &header("siege")?);
+        rules.siege = SiegeRule {
+            enabled: siege.next()?,
+            health: siege.next()?,
+            torpedo_damage: siege.next()?,
+            bonus: siege.next()?,
+            defender_seconds: siege.next()?,
+            max_defenders: siege.next()?,
+        };
+        let mut base = Fields::new("base", &header("base")?);
+        rules.base = BaseConfig {
+            shield_seconds: base.next()?,
+            shield_radius: base.next()?,
+            repel_impulse: base.next()?,
+            turret: base.next()?,
+            turret_radius: base.next()?,
+            turret_seconds: base.next()?,
+        };
+        let mut director = Fields::new("director", &header("director")?);
+        rules.director = DirectorRule {
+            enabled: director.next()?,
+            start_seconds: director.next()?,
+            min_seconds: director.next()?,
+            glod_pressure: director.next()?,
+            time_pressure: director.next()?,
+            max_enemies: director.next()?,
+        };
+        let mut enemy_fire = Fields::new("enemy-fire", &header("enemy-fire")?);
+        rules.enemy_fire = EnemyFireRule {
+            enabled: enemy_fire.next()?,
+            range: enemy_fire.next()?,
+            min_range: enemy_fire.next()?,
+            cone: enemy_fire.next()?,
+            cooldown_seconds: enemy_fire.next()?,
+            recoil_seconds: enemy_fire.next()?,
+        };
+        let mut asteroids = Fields::new("asteroids", &header("asteroids")?);
+        rules.asteroids = AsteroidRule {
+            enabled: asteroids.next()?,
+            count: asteroids.next()?,
+            split_chance: asteroids.next()?,
+        };
+        let mut hazards = Fields::new("hazards", &header("hazards")?);
+        rules.hazards = HazardRule {
+            enabled: hazards.next()?,
+            count: hazards.next()?,
+        };
+        let mut fog = Fields::new("fog", &header("fog")?);
+        rules.fog = FogRule {
+            enabled: fog.next()?,
+            sensor_radius: fog.next()?,
+            flare_radius: fog.next()?,
+            flare_seconds: fog.next()?,
+            ping_seconds: fog.next()?,
+            ping_recharge_seconds: fog.next()?,
+        };
+        let level_lines: usize = Fields::new("level", &header("level")?).next()?;
+        let level_text: Vec<&str> = lines.by_ref().take(level_lines).collect();
+        let level = match level_lines {
+            0 => None,
+            _ => Some(
+                Level::parse(&level_text.join("\n"))
+                    .map_err(|err| format!("bad level in replay: {}", err))?,
+            ),
+        };
+
+        let frames = lines
+            .map(|line| {
+                line.split(',')
+                    .map(|bits| bits.trim().parse::<u8>())
+                    .collect::<Result<Vec<u8>, _>>()
+                    .map_err(|_| format!("bad replay frame: {}", line))
+            })
+            .collect::<Result<Vec<Vec<u8>>, String>>()?;
+        Ok(Replay {
+            seed,
+            difficulty,
+            mode,
+            game_mode,
+            upgrades,
+            rules,
+            level,
+            frames,
+        })
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = format!(
+            "seed {}\ndifficulty {}\nmode {}\ngame-mode {}\nupgrades {}\n",
+            self.seed.0,
+            self.difficulty.name(),
+            self.mode.name(),
+            self.game_mode.key(),
+            self.upgrades.to_storage()
+        );
+        let rules = &self.rules;
+        text += &format!(
+            "deposit {} {}\n",
+            rules.deposit.enabled, rules.deposit.victory_minimum
+        );
+        text += &format!(
+            "release {} {} {} {} {} {}\n",
+            rules.release.trigger.key(),
+            rules.release.delay_seconds,
+            rules.release.glod,
+            rules.release.noise_radius,
+            rules.release.stages,
+            rules.release.stage_seconds,
+        );
+        text += &format!(
+            "siege {} {} {} {} {} {}\n",
+            rules.siege.enabled,
+            rules.siege.health,
+            rules.siege.torpedo_damage,
+            rules.siege.bonus,
+            rules.siege.defender_seconds,
+            rules.siege.max_defenders,
+        );
+        text += &format!(
+            "base {} {} {} {} {} {}\n",
+            rules.base.shield_seconds,
+            rules.base.shield_radius,
+            rules.base.repel_impulse,
+            rules.base.turret,
+            rules.base.turret_radius,
+            rules.base.turret_seconds,
+        );
+        text += &format!(
+            "director {} {} {} {} {} {}\n",
+            rules.director.enabled,
+            rules.director.start_seconds,
+            rules.director.min_seconds,
+            rules.director.glod_pressure,
+            rules.director.time_pressure,
+            rules.director.max_enemies,
+        );
+        text += &format!(
+            "enemy-fire {} {} {} {} {} {}\n",
+            rules.enemy_fire.enabled,
+            rules.enemy_fire.range,
+            rules.enemy_fire.min_range,
+            rules.enemy_fire.cone,
+            rules.enemy_fire.cooldown_seconds,
+            rules.enemy_fire.recoil_seconds,
+        );
+        text += &format!(
+            "asteroids {} {} {}\n",
+            rules.asteroids.enabled, rules.asteroids.count, rules.asteroids.split_chance,
+        );
+        text += &format!(
+            "hazards {} {}\n",
+            rules.hazards.enabled, rules.hazards.count,
+        );
+        text += &format!(
+            "fog {} {} {} {} {} {}\n",
+            rules.fog.enabled,
+            rules.fog.sensor_radius,
+            rules.fog.flare_radius,
+            rules.fog.flare_seconds,
+            rules.fog.ping_seconds,
+            rules.fog.ping_recharge_seconds,
+        );
+        let level = self.level.as_ref().map(Level::to_text).unwrap_or_default();
+        text += &format!("level {}\n{}", level.lines().count(), level);
+        for frame in &self.frames {
+            let bits: Vec<String> = frame.iter().map(|bits| bits.to_string()).collect();
+            text += &bits.join(",");
+            text += "\n";
+        }
+        text
+    }
+}
+
+/// Frame of the current game, counted from when the ships spawn.
+#[derive(Resource, Default)]
+struct ReplayFrame(usize);
+
+/// Where to write the inputs of each game.
+#[derive(Resource)]
+struct RecordTo(PathBuf);
+
+/// Inputs of the game in progress, kept apart from any replay being played.
+#[derive(Resource)]
+struct Recording(Replay);
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let cli = app.world.resource::<Cli>().clone();
+        app.insert_resource(ReplayFrame::default())
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(reset_frame));
+        if let Some(path) = cli.replay {
+            let replay = Replay::load(&path).unwrap_or_else(|err| exit_with(&err));
+            // The recorded level replaces any given with `--level`.
+            app.world.remove_resource::<Level>();
+            if let Some(level) = &replay.level {
+                app.insert_resource(level.clone());
+            }
+            app.insert_resource(replay)
+                .add_startup_system(apply_replay_settings)
+                .add_system_set(
+                    SystemSet::on_update(GameState::Game)
+                        .with_system(play_inputs.label("inputs").after("controls")),
+                );
+        }
+        if let Some(path) = cli.record {
+            app.insert_resource(RecordTo(path))
+                .add_system_set(SystemSet::on_enter(GameState::Game).with_system(start_recording))
+                .add_system_set(
+                    SystemSet::on_update(GameState::Game)
+                        .with_system(record_inputs.label("record").after("inputs")),
+                )
+                .add_system_set(SystemSet::on_exit(GameState::Game).with_system(save_recording));
+        }
+        app.add_system_set(
+            SystemSet::on_update(GameState::Game)
+                .with_system(next_frame.after("inputs").after("record")),
+        );
+    }
+}
+
+fn reset_frame(mut frame: ResMut<ReplayFrame>) {
+    frame.0 = 0;
+}
+
+fn next_frame(mut frame: ResMut<ReplayFrame>) {
+    frame.0 += 1;
+}
+
+fn apply_replay_settings(
+    replay: Res<Replay>,
+    mut game_seed: ResMut<GameSeed>,
+    mut difficulty: ResMut<Difficulty>,
+    mut mode: ResMut<PlayerMode>,
+    mut game_mode: ResMut<GameMode>,
+    mut upgrades: ResMut<Upgrades>,
+    mut rules: Rules,
+) {
+    *game_seed = replay.seed.clone();
+    *difficulty = replay.difficulty;
+    *mode = replay.mode;
+    *game_mode = replay.game_mode;
+    *upgrades = replay.upgrades.clone();
+    rules.set(&replay.rules);
+}
+
+fn play_inputs(
+    replay: Res<Replay>,
+    frame: Res<ReplayFrame>,
+    mut ships: Query<(&Pilot, &mut ShipInput), With<Playable>>,
+) {
+    let recorded = replay.frames.get(frame.0);
+    for (pilot, mut input) in ships.iter_mut() {
+        let bits = recorded.and_then(|bits| bits.get(pilot.0)).copied();
+        *input = ShipInput::from_bits(bits.unwrap_or(0));
+    }
+}
+
+fn start_recording(
+    mut commands: Commands,
+    game_seed: Res<GameSeed>,
+    difficulty: Res<Difficulty>,
+    mode: Res<PlayerMode>,
+    game_mode: Res<GameMode>,
+    upgrades: Res<Upgrades>,
+    rules: Rules,
+    level: Option<Res<Level>>,
+) {
+    commands.insert_resource(Recording(Replay {
+        seed: game_seed.clone(),
+        difficulty: *difficulty,
+        mode: *mode,
+        game_mode: *game_mode,
+        upgrades: upgrades.clone(),
+        rules: rules.get(),
+        level: level.map(|level| level.clone()),
+        frames: Vec::new(),
+    }));
+}
+
+fn record_inputs(
+    recording: Option<ResMut<Recording>>,
+    ships: Query<(&Pilot, &ShipInput), With<Playable>>,
+) {
+    let Some(mut recording) = recording else {
+        return;
+    };
+    let mut inputs = vec![0; recording.0.mode.players()];
+    for (pilot, input) in ships.iter() {
+        if let Some(bits) = inputs.get_mut(pilot.0) {
+            *bits = input.to_bits();
+        }
+    }
+    recording.0.frames.push(inputs);
+}
+
+fn save_recording(recording: Option<Res<Recording>>, record_to: Res<RecordTo>) {
+    if let Some(recording) = recording {
+        if let Err(err) = std::fs::write(&record_to.0, recording.0.to_text()) {
+            error!(
+                "could not save replay to {}: {}",
+                record_to.0.display(),
+                err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> Replay {
+        Replay {
+            seed: GameSeed("abc-123".to_string()),
+            difficulty: Difficulty::Hard,
+            mode: PlayerMode::Versus,
+            game_mode: GameMode::ScoreAttack,
+            upgrades: Upgrades::from_storage("1,0,2"),
+            rules: RuleSet {
+                deposit: DepositRule {
+                    enabled: true,
+                    victory_minimum: 15,
+                },
+                release: ReleaseRule {
+                    trigger: ReleaseTrigger::Noise,
+                    stages: 2,
+                    ..default()
+                },
+                ..default()
+            },
+            level: Some(Level::parse("foe 200 200\nglod 0 10 rare\n").unwrap()),
+            frames: vec![vec![0, 5], vec![12, 17]],
+        }
+    }
+
+    #[test]
+    fn parse_reads_back_to_text() {
+        let replay = replay();
+        let parsed = Replay::parse(&replay.to_text()).unwrap();
+        assert_eq!(parsed.seed, replay.seed);
+        assert_eq!(parsed.difficulty, replay.difficulty);
+        assert_eq!(parsed.mode, replay.mode);
+        assert_eq!(parsed.game_mode, replay.game_mode);
+        assert_eq!(parsed.upgrades, replay.upgrades);
+        assert_eq!(parsed.rules, replay.rules);
+        let level_text = |replay: &Replay| replay.level.as_ref().map(Level::to_text);
+        assert_eq!(level_text(&parsed), level_text(&replay));
+        assert_eq!(parsed.frames, replay.frames);
+    }
+
+    #[test]
+    fn parse_reads_a_replay_without_a_level() {
+        let replay = Replay {
+            level: None,
+            ..replay()
+        };
+        let parsed = Replay::parse(&replay.to_text()).unwrap();
+        assert!(parsed.level.is_none());
+        assert_eq!(parsed.frames, replay.frames);
+    }
+
+    #[test]
+    fn parse_rejects_bad_replays() {
+        let text = replay().to_text();
+        assert!(Replay::parse("").is_err());
+        assert!(Replay::parse(&text.replace("seed abc-123", "seed abc/123")).is_err());
+        assert!(Replay::parse(&text.replace("difficulty Hard", "difficulty Brutal")).is_err());
+        assert!(Replay::parse(&text.replace("game-mode", "gamemode")).is_err());
+        assert!(Replay::parse(&text.replace("deposit true", "deposit maybe")).is_err());
+        assert!(Replay::parse(&text.replace("level 2", "level 3")).is_err());
+        assert!(Replay::parse(&(text + "1,x\n")).is_err());
+    }
+
+    #[test]
+    fn playback_feeds_each_pilot_its_recorded_inputs() {
+        let mut app = App::new();
+        app.insert_resource(replay())
+            .insert_resource(ReplayFrame::default())
+            .add_system(play_inputs.label("inputs"))
+            .add_system(next_frame.after("inputs"));
+        let ships: Vec<Entity> = (0..2)
+            .map(|pilot| {
+                let ship = (Pilot(pilot), ShipInput::default(), Playable);
+                app.world.spawn(ship).id()
+            })
+            .collect();
+        let mut played = || {
+            app.update();
+            ships
+                .iter()
+                .map(|ship| app.world.get::<ShipInput>(*ship).unwrap().to_bits())
+                .collect::<Vec<u8>>()
+        };
+        assert_eq!(played(), [0, 5]);
+        assert_eq!(played(), [12, 17]);
+        assert_eq!(played(), [0, 0]);
+    }
+}
@@ -0,0 +1,644 @@
+use crate::{GLOD_MASS, GLOD_QUANTITY};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use std::marker::PhantomData;
+
+pub const MAX_PLAYERS: usize = 2;
+
+pub struct RulesPlugin;
+
+impl Plugin for RulesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Difficulty::Normal)
+            .insert_resource(DepositRule::default())
+            .insert_resource(ReleaseRule::default())
+            .insert_resource(SiegeRule::default())
+            .insert_resource(BaseConfig::default())
+            .insert_resource(DirectorRule::default())
+            .insert_resource(EnemyFireRule::default())
+            .insert_resource(AsteroidRule::default())
+            .insert_resource(HazardRule::default())
+            .insert_resource(FogRule::default())
+            .insert_resource(PlayerMode::Solo)
+            .insert_resource(GameMode::Classic);
+    }
+}
+
+/// The optional rules from the options screen, for systems that handle them all.
+#[derive(SystemParam)]
+pub struct Rules<'w, 's> {
+    pub deposit: ResMut<'w, DepositRule>,
+    pub release: ResMut<'w, ReleaseRule>,
+    pub siege: ResMut<'w, SiegeRule>,
+    pub base: ResMut<'w, BaseConfig>,
+    pub director: ResMut<'w, DirectorRule>,
+    pub enemy_fire: ResMut<'w, EnemyFireRule>,
+    pub asteroids: ResMut<'w, AsteroidRule>,
+    pub hazards: ResMut<'w, HazardRule>,
+    pub fog: ResMut<'w, FogRule>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl Rules<'_, '_> {
+    pub fn is_changed(&self) -> bool {
+        self.deposit.is_changed()
+            || self.release.is_changed()
+            || self.siege.is_changed()
+            || self.base.is_changed()
+            || self.director.is_changed()
+            || self.enemy_fire.is_changed()
+            || self.asteroids.is_changed()
+            || self.hazards.is_changed()
+            || self.fog.is_changed()
+    }
+
+    /// A copy of every rule, to hand on or put back later.
+    pub fn get(&self) -> RuleSet {
+        RuleSet {
+            deposit: *self.deposit,
+            release: *self.release,
+            siege: *self.siege,
+            base: *self.base,
+            director: *self.director,
+            enemy_fire: *self.enemy_fire,
+            asteroids: *self.asteroids,
+            hazards: *self.hazards,
+            fog: *self.fog,
+        }
+    }
+
+    pub fn set(&mut self, rules: &RuleSet) {
+        *self.deposit = rules.deposit;
+        *self.release = rules.release;
+        *self.siege = rules.siege;
+        *self.base = rules.base;
+        *self.director = rules.director;
+        *self.enemy_fire = rules.enemy_fire;
+        *self.asteroids = rules.asteroids;
+        *self.hazards = rules.hazards;
+        *self.fog = rules.fog;
+    }
+}
+
+/// The values of `Rules`, kept apart from the resources.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RuleSet {
+    pub deposit: DepositRule,
+    pub release: ReleaseRule,
+    pub siege: SiegeRule,
+    pub base: BaseConfig,
+    pub director: DirectorRule,
+    pub enemy_fire: EnemyFireRule,
+    pub asteroids: AsteroidRule,
+    pub hazards: HazardRule,
+    pub fog: FogRule,
+}
+
+#[derive(Resource, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn next(&self) -> Difficulty {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard]
+            .into_iter()
+            .find(|difficulty| difficulty.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Mass each carried common glod adds to the ship.
+    pub fn glod_mass(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.5 * GLOD_MASS,
+            Difficulty::Normal => GLOD_MASS,
+            Difficulty::Hard => 1.5 * GLOD_MASS,
+        }
+    }
+
+    /// Upper bound on the mass carried glod can add, if any.
+    pub fn glod_mass_cap(&self) -> Option<f32> {
+        match self {
+            Difficulty::Easy => Some(10.0 * GLOD_MASS),
+            Difficulty::Normal => Some(20.0 * GLOD_MASS),
+            Difficulty::Hard => None,
+        }
+    }
+
+    /// Mass added by cargo weighing `weight` common glod.
+    pub fn cargo_mass(&self, weight: u32) -> f32 {
+        let mass = weight as f32 * self.glod_mass();
+        match self.glod_mass_cap() {
+            Some(cap) => f32::min(mass, cap),
+            None => mass,
+        }
+    }
+
+    /// Fraction of the heaviest load the ship can end up carrying.
+    pub fn load_fraction(&self, weight: u32) -> f32 {
+        let heaviest = self
+            .glod_mass_cap()
+            .unwrap_or(GLOD_QUANTITY as f32 * self.glod_mass());
+        self.cargo_mass(weight) / heaviest
+    }
+}
+
+/// When enabled, glod only scores once carried back to the friendly base.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct DepositRule {
+    pub enabled: bool,
+    pub victory_minimum: u8,
+}
+
+impl Default for DepositRule {
+    fn default() -> DepositRule {
+        DepositRule {
+            enabled: false,
+            victory_minimum: 10,
+        }
+    }
+}
+
+impl DepositRule {
+    pub fn next_minimum(&self) -> u8 {
+        (self.victory_minimum + 5) % (GLOD_QUANTITY as u8 + 5)
+    }
+
+    pub fn allows_victory(&self, banked: u32) -> bool {
+        !self.enabled || banked >= self.victory_minimum as u32
+    }
+}
+
+/// What a friendly base does for ships that return to it.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct BaseConfig {
+    /// How long the base keeps the enemy out after each return; zero for never.
+    pub shield_seconds: f32,
+    pub shield_radius: f32,
+    /// Impulse pushing an enemy back out of the shield each frame.
+    pub repel_impulse: f32,
+    pub turret: bool,
+    pub turret_radius: f32,
+    /// Time between turret shots.
+    pub turret_seconds: f32,
+}
+
+impl Default for BaseConfig {
+    fn default() -> BaseConfig {
+        BaseConfig {
+            shield_seconds: 5.0,
+            shield_radius: 90.0,
+            repel_impulse: 40.0,
+            turret: false,
+            turret_radius: 160.0,
+            turret_seconds: 2.0,
+        }
+    }
+}
+
+impl BaseConfig {
+    pub fn next_shield_seconds(&self) -> f32 {
+        (self.shield_seconds + 5.0) % 15.0
+    }
+}
+
+/// When enabled, waves of enemies keep coming once the first is released.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct DirectorRule {
+    pub enabled: bool,
+    /// Time between waves at the start, before any glod is held.
+    pub start_seconds: f32,
+    /// Shortest time between waves however hard it gets.
+    pub min_seconds: f32,
+    /// Seconds taken off the wait for each point of glod held or banked.
+    pub glod_pressure: f32,
+    /// Seconds taken off the wait for each second since release.
+    pub time_pressure: f32,
+    /// Most enemies the director keeps on the field.
+    pub max_enemies: usize,
+}
+
+impl Default for DirectorRule {
+    fn default() -> DirectorRule {
+        DirectorRule {
+            enabled: false,
+            start_seconds: 25.0,
+            min_seconds: 6.0,
+            glod_pressure: 0.5,
+            time_pressure: 0.1,
+            max_enemies: 8,
+        }
+    }
+}
+
+impl DirectorRule {
+    /// Wait before the next wave, `elapsed` seconds after release with `glod` held,
+    /// banked or carried.
+    pub fn wave_interval(&self, elapsed: f32, glod: u32) -> f32 {
+        let pressure = self.glod_pressure * glod as f32 + self.time_pressure * elapsed;
+        f32::max(self.start_seconds - pressure, self.min_seconds)
+    }
+}
+
+/// When enabled, enemies launch torpedoes at ships lined up ahead of them.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct EnemyFireRule {
+    pub enabled: bool,
+    /// Farthest a target can be and still be fired at.
+    pub range: f32,
+    /// Closest a target can be, so an enemy doesn't catch its own blast.
+    pub min_range: f32,
+    /// Largest bearing, in radians, at which an enemy will fire.
+    pub cone: f32,
+    /// Time an enemy takes to reload.
+    pub cooldown_seconds: f32,
+    /// Time an enemy stops chasing after each launch.
+    pub recoil_seconds: f32,
+}
+
+impl Default for EnemyFireRule {
+    fn default() -> EnemyFireRule {
+        EnemyFireRule {
+            enabled: false,
+            range: 220.0,
+            min_range: 60.0,
+            cone: 0.15,
+            cooldown_seconds: 4.0,
+            recoil_seconds: 0.8,
+        }
+    }
+}
+
+/// When enabled, seeded asteroids drift about the arena.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct AsteroidRule {
+    pub enabled: bool,
+    pub count: usize,
+    /// Chance that an asteroid breaks up when caught in an explosion.
+    pub split_chance: f64,
+}
+
+impl Default for AsteroidRule {
+    fn default() -> AsteroidRule {
+        AsteroidRule {
+            enabled: false,
+            count: 8,
+            split_chance: 0.5,
+        }
+    }
+}
+
+/// When enabled, seeded gravity wells, repulsors, currents and nebulae fill the arena.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct HazardRule {
+    pub enabled: bool,
+    pub count: usize,
+}
+
+impl Default for HazardRule {
+    fn default() -> HazardRule {
+        HazardRule {
+            enabled: false,
+            count: 4,
+        }
+    }
+}
+
+/// When enabled, glod and enemies are hidden unless something is close enough to see them.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct FogRule {
+    pub enabled: bool,
+    /// How far a ship or base can see.
+    pub sensor_radius: f32,
+    /// Area an explosion lights up, and for how long.
+    pub flare_radius: f32,
+    pub flare_seconds: f32,
+    /// How long a sonar ping shows the whole map, and how long it takes to recharge.
+    pub ping_seconds: f32,
+    pub ping_recharge_seconds: f32,
+}
+
+impl Default for FogRule {
+    fn default() -> FogRule {
+        FogRule {
+            enabled: false,
+            sensor_radius: 120.0,
+            flare_radius: 90.0,
+            flare_seconds: 1.5,
+            ping_seconds: 2.0,
+            ping_recharge_seconds: 12.0,
+        }
+    }
+}
+
+/// When enabled, the enemy base can be destroyed with torpedoes for a bonus victory.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct SiegeRule {
+    pub enabled: bool,
+    pub health: u32,
+    /// Damage from a torpedo exploding at the base.
+    pub torpedo_damage: u32,
+    /// Glod awarded to the team that destroys the base.
+    pub bonus: u8,
+    /// Time between defenders while the enemy is out.
+    pub defender_seconds: f32,
+    pub max_defenders: usize,
+}
+
+impl Default for SiegeRule {
+    fn default() -> SiegeRule {
+        SiegeRule {
+            enabled: false,
+            health: 100,
+            torpedo_damage: 10,
+            bonus: 20,
+            defender_seconds: 15.0,
+            max_defenders: 3,
+        }
+    }
+}
+
+/// What wakes the enemy up.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ReleaseTrigger {
+    /// The first time a ship leaves its base.
+    LeaveBase,
+    /// After a fixed time.
+    Timer,
+    /// Once enough glod has been collected.
+    Glod,
+    /// When a ship thrusts or fires near the enemy.
+    Noise,
+}
+
+impl ReleaseTrigger {
+    pub fn next(&self) -> ReleaseTrigger {
+        match self {
+            ReleaseTrigger::LeaveBase => ReleaseTrigger::Timer,
+            ReleaseTrigger::Timer => ReleaseTrigger::Glod,
+            ReleaseTrigger::Glod => ReleaseTrigger::Noise,
+            ReleaseTrigger::Noise => ReleaseTrigger::LeaveBase,
+        }
+    }
+
+    /// Name without spaces, for replay files.
+    pub fn key(&self) -> &'static str {
+        match self {
+            ReleaseTrigger::LeaveBase => "leave-base",
+            ReleaseTrigger::Timer => "timer",
+            ReleaseTrigger::Glod => "glod",
+            ReleaseTrigger::Noise => "noise",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<ReleaseTrigger> {
+        [
+            ReleaseTrigger::LeaveBase,
+            ReleaseTrigger::Timer,
+            ReleaseTrigger::Glod,
+            ReleaseTrigger::Noise,
+        ]
+        .into_iter()
+        .find(|trigger| trigger.key() == key)
+    }
+}
+
+/// How and when the enemy is released.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct ReleaseRule {
+    pub trigger: ReleaseTrigger,
+    pub delay_seconds: f32,
+    pub glod: usize,
+    pub noise_radius: f32,
+    /// Extra enemies waiting at the enemy base, released one at a time.
+    pub stages: usize,
+    pub stage_seconds: f32,
+}
+
+impl Default for ReleaseRule {
+    fn default() -> ReleaseRule {
+        ReleaseRule {
+            trigger: ReleaseTrigger::LeaveBase,
+            delay_seconds: 10.0,
+            glod: 5,
+            noise_radius: 150.0,
+            stages: 0,
+            stage_seconds: 8.0,
+        }
+    }
+}
+
+impl ReleaseRule {
+    pub const MAX_STAGES: usize = 3;
+
+    pub fn next_stages(&self) -> usize {
+        (self.stages + 1) % (ReleaseRule::MAX_STAGES + 1)
+    }
+
+    pub fn describe(&self) -> String {
+        match self.trigger {
+            ReleaseTrigger::LeaveBase => "on leaving base".to_string(),
+            ReleaseTrigger::Timer => format!("after {:.0}s", self.delay_seconds),
+            ReleaseTrigger::Glod => format!("after {} glod", self.glod),
+            ReleaseTrigger::Noise => "on noise".to_string(),
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum PlayerMode {
+    Solo,
+    Versus,
+    Coop,
+}
+
+impl PlayerMode {
+    pub fn next(&self) -> PlayerMode {
+        match self {
+            PlayerMode::Solo => PlayerMode::Versus,
+            PlayerMode::Versus => PlayerMode::Coop,
+            PlayerMode::Coop => PlayerMode::Solo,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlayerMode::Solo => "Solo",
+            PlayerMode::Versus => "Versus",
+            PlayerMode::Coop => "Co-op",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<PlayerMode> {
+        [PlayerMode::Solo, PlayerMode::Versus, PlayerMode::Coop]
+            .into_iter()
+            .find(|mode| mode.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn players(&self) -> usize {
+        match self {
+            PlayerMode::Solo => 1,
+            PlayerMode::Versus | PlayerMode::Coop => 2,
+        }
+    }
+
+    pub fn teams(&self) -> usize {
+        match self {
+            PlayerMode::Solo | PlayerMode::Coop => 1,
+            PlayerMode::Versus => 2,
+        }
+    }
+
+    /// Team a player belongs to; teams own a base and a score.
+    pub fn team_of(&self, player: usize) -> usize {
+        match self {
+            PlayerMode::Solo | PlayerMode::Coop => 0,
+            PlayerMode::Versus => player,
+        }
+    }
+
+    /// Enemies released per game, scaled up when players cooperate.
+    pub fn enemies(&self) -> usize {
+        match self {
+            PlayerMode::Solo | PlayerMode::Versus => 1,
+            PlayerMode::Coop => 2,
+        }
+    }
+}
+
+/// What a game is won on, each with its own leaderboard.
+#[derive(Resource, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum GameMode {
+    /// Leave the base, let the enemy out, and make it back.
+    Classic,
+    /// Collect every glod as fast as possible.
+    TimeAttack,
+    /// Collect as much glod as possible before time runs out.
+    ScoreAttack,
+    /// Last as long as possible against growing waves of enemies.
+    Survival,
+}
+
+pub const GAME_MODES: [GameMode; 4] = [
+    GameMode::Classic,
+    GameMode::TimeAttack,
+    GameMode::ScoreAttack,
+    GameMode::Survival,
+];
+
+impl GameMode {
+    pub fn next(&self) -> GameMode {
+        match self {
+            GameMode::Classic => GameMode::TimeAttack,
+            GameMode::TimeAttack => GameMode::ScoreAttack,
+            GameMode::ScoreAttack => GameMode::Survival,
+            GameMode::Survival => GameMode::Classic,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "Classic",
+            GameMode::TimeAttack => "Time attack",
+            GameMode::ScoreAttack => "Score attack",
+            GameMode::Survival => "Survival",
+        }
+    }
+
+    /// Name without spaces, for storage keys and the command line.
+    pub fn key(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "classic",
+            GameMode::TimeAttack => "time-attack",
+            GameMode::ScoreAttack => "score-attack",
+            GameMode::Survival => "survival",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        GAME_MODES.into_iter().find(|mode| {
+            mode.key().eq_ignore_ascii_case(name) || mode.name().eq_ignore_ascii_case(name)
+        })
+    }
+
+    /// Whether results are times rather than scores.
+    pub fn is_timed(&self) -> bool {
+        matches!(self, GameMode::TimeAttack | GameMode::Survival)
+    }
+
+    /// Whether a smaller result ranks higher.
+    pub fn lower_is_better(&self) -> bool {
+        *self == GameMode::TimeAttack
+    }
+
+    /// Shows a result, in milliseconds for timed modes and points otherwise.
+    pub fn format_result(&self, value: u32) -> String {
+        if self.is_timed() {
+            format!("{:.1}s", value as f32 / 1000.0)
+        } else {
+            value.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deposit_minimum_only_counts_when_enabled() {
+        let mut rule = DepositRule::default();
+        assert!(rule.allows_victory(0));
+        rule.enabled = true;
+        assert!(!rule.allows_victory(9));
+        assert!(rule.allows_victory(10));
+    }
+
+    #[test]
+    fn deposit_minimum_cycles_up_to_every_glod() {
+        let mut rule = DepositRule {
+            enabled: true,
+            victory_minimum: 0,
+        };
+        let mut seen = vec![0];
+        while rule.next_minimum() != 0 {
+            rule.victory_minimum = rule.next_minimum();
+            seen.push(rule.victory_minimum as usize);
+        }
+        assert_eq!(seen.last(), Some(&GLOD_QUANTITY));
+        assert!(seen.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn waves_come_sooner_with_glod_and_time() {
+        let rule = DirectorRule::default();
+        assert_eq!(rule.wave_interval(0.0, 0), rule.start_seconds);
+        assert!(rule.wave_interval(0.0, 10) < rule.wave_interval(0.0, 0));
+        assert!(rule.wave_interval(30.0, 0) < rule.wave_interval(0.0, 0));
+    }
+
+    #[test]
+    fn waves_never_come_faster_than_the_minimum() {
+        let rule = DirectorRule::default();
+        assert_eq!(rule.wave_interval(1000.0, 1000), rule.min_seconds);
+        assert_eq!(rule.wave_interval(0.0, u32::MAX), rule.min_seconds);
+    }
+}
@@ -0,0 +1,155 @@
+//! Sharing seeds: the daily challenge, clipboard and links.
+#[cfg(target_family = "wasm")]
+use crate::AppState;
+use crate::{GameSeed, GameState};
+use bevy::prelude::*;
+
+pub struct SharePlugin;
+
+impl Plugin for SharePlugin {
+    fn build(&self, app: &mut App) {
+        #[cfg(target_family = "wasm")]
+        app.add_startup_system(load_shared_seed);
+        app.add_system_set(SystemSet::on_enter(GameState::Game).with_system(publish_seed));
+    }
+}
+
+impl GameSeed {
+    /// The same seed for everyone on a given UTC day.
+    pub fn daily() -> GameSeed {
+        let (year, month, day) = civil_from_days(unix_days());
+        GameSeed(format!("daily-{:04}-{:02}-{:02}", year, month, day))
+    }
+}
+
+#[cfg(target_family = "wasm")]
+fn unix_days() -> i64 {
+    (js_sys::Date::now() / 86_400_000.0).floor() as i64
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn unix_days() -> i64 {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    (secs / 86_400) as i64
+}
+
+/// Converts days since 1970-01-01 to a proleptic Gregorian (year, month, day).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Seed passed in by a `?seed=` link; native builds take `--seed` instead.
+#[cfg(target_family = "wasm")]
+pub fn shared_seed() -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    let params = web_sys::UrlSearchParams::new_with_str(&search).ok()?;
+    params.get("seed").filter(|seed| !seed.is_empty())
+}
+
+/// Puts the seed in the address bar, so the page link loads the same map.
+#[cfg(target_family = "wasm")]
+fn set_seed_link(seed: &str) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let Ok(params) = web_sys::UrlSearchParams::new() else {
+        return;
+    };
+    params.set("seed", seed);
+    let url = format!("?{}", String::from(params.to_string()));
+    if let Ok(history) = window.history() {
+        let _ = history.replace_state_with_url(&js_sys::Object::new(), "", Some(&url));
+    }
+}
+
+/// Makes the seed available to share, returning a note for the player.
+/// On wasm the page link is updated instead of the clipboard.
+#[cfg(target_family = "wasm")]
+pub fn copy_seed(seed: &str) -> &'static str {
+    set_seed_link(seed);
+    "Share the page link to share this seed"
+}
+
+/// Makes the seed available to share, returning a note for the player.
+/// Uses whichever clipboard tool is installed, finishing off in the background
+/// since some tools only exit once the clipboard is taken over.
+#[cfg(not(target_family = "wasm"))]
+pub fn copy_seed(seed: &str) -> &'static str {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    use std::thread;
+
+    const CLIPBOARD_TOOLS: [(&str, &[&str]); 4] = [
+        ("wl-copy", &[]),
+        ("xclip", &["-selection", "clipboard"]),
+        ("pbcopy", &[]),
+        ("clip.exe", &[]),
+    ];
+    for (tool, args) in CLIPBOARD_TOOLS {
+        let Ok(mut child) = Command::new(tool).args(args).stdin(Stdio::piped()).spawn() else {
+            continue;
+        };
+        let seed = seed.to_string();
+        thread::spawn(move || {
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = stdin.write_all(seed.as_bytes());
+            }
+            let _ = child.wait();
+        });
+        return "Seed copied";
+    }
+    "No clipboard available"
+}
+
+/// Reads text from the clipboard. Browsers only allow this asynchronously, so wasm has no paste.
+#[cfg(target_family = "wasm")]
+pub fn paste_text() -> Option<String> {
+    None
+}
+
+/// Reads text from the clipboard, using whichever clipboard tool is installed.
+#[cfg(not(target_family = "wasm"))]
+pub fn paste_text() -> Option<String> {
+    use std::process::Command;
+
+    const CLIPBOARD_TOOLS: [(&str, &[&str]); 4] = [
+        ("wl-paste", &["--no-newline"]),
+        ("xclip", &["-selection", "clipboard", "-o"]),
+        ("pbpaste", &[]),
+        ("powershell.exe", &["-command", "Get-Clipboard"]),
+    ];
+    CLIPBOARD_TOOLS.into_iter().find_map(|(tool, args)| {
+        let output = Command::new(tool).args(args).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8(output.stdout).ok()
+    })
+}
+
+#[cfg(target_family = "wasm")]
+fn load_shared_seed(mut game_seed: ResMut<GameSeed>, mut app_state: ResMut<State<AppState>>) {
+    if let Some(seed) = shared_seed() {
+        *game_seed = GameSeed(seed);
+        app_state.set(AppState::InGame).unwrap();
+    }
+}
+
+#[cfg(target_family = "wasm")]
+fn publish_seed(game_seed: Res<GameSeed>) {
+    set_seed_link(&game_seed.0);
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn publish_seed() {}
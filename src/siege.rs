@@ -0,0 +1,150 @@
+//! The optional objective of destroying the enemy base with torpedoes.
+use crate::{
+    spawn_enemy, win, EndGate, EnemyBase, EnemyState, Explosion, GameMode, GameState, GameTime,
+    PlayerMode, Score, SiegeRule, Team, Winner,
+};
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+
+pub struct SiegePlugin;
+
+impl Plugin for SiegePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DefenderTimer(Timer::from_seconds(
+            1.0,
+            TimerMode::Repeating,
+        )))
+        .add_system_set(SystemSet::on_enter(GameState::Game).with_system(reset_defenders))
+        .add_system_set(
+            SystemSet::on_update(GameState::Game)
+                .with_system(damage_enemy_base.label("siege"))
+                .with_system(spawn_defenders.after("siege"))
+                .with_system(siege_victory.label("victory").after("siege")),
+        );
+    }
+}
+
+/// Half the side of the enemy base's square.
+pub const ENEMY_BASE_HALF_SIZE: f32 = 40.0;
+const DEFENDER_SPREAD: f32 = 30.0;
+
+#[derive(Component, Clone)]
+pub struct BaseHealth {
+    pub current: u32,
+    pub max: u32,
+    /// Team whose torpedo hit last, credited if the base falls.
+    pub last_hit: Option<usize>,
+    /// Set once the fall has been paid for, in modes it doesn't end.
+    pub fallen: bool,
+}
+
+impl BaseHealth {
+    pub fn new(max: u32) -> BaseHealth {
+        BaseHealth {
+            current: max,
+            max,
+            last_hit: None,
+            fallen: false,
+        }
+    }
+
+    pub fn fraction(&self) -> f32 {
+        self.current as f32 / self.max.max(1) as f32
+    }
+}
+
+/// An enemy sent out by the enemy base to protect it.
+#[derive(Component, Clone)]
+pub struct Defender;
+
+#[derive(Resource, Clone)]
+pub struct DefenderTimer(Timer);
+
+fn reset_defenders(rule: Res<SiegeRule>, mut timer: ResMut<DefenderTimer>) {
+    *timer = DefenderTimer(Timer::from_seconds(
+        rule.defender_seconds,
+        TimerMode::Repeating,
+    ));
+}
+
+fn damage_enemy_base(
+    rule: Res<SiegeRule>,
+    // Only torpedoes fired by a team count, so anything else leaves the base alone.
+    explosions: Query<(&Transform, &Explosion, &Team), Added<Explosion>>,
+    mut base: Query<(&Transform, &mut BaseHealth), With<EnemyBase>>,
+) {
+    if !rule.enabled {
+        return;
+    }
+    for (base_trans, mut health) in base.iter_mut() {
+        for (trans, explosion, team) in explosions.iter() {
+            let reach = explosion.radius + ENEMY_BASE_HALF_SIZE;
+            if trans.translation.distance(base_trans.translation) <= reach {
+                health.current = health.current.saturating_sub(rule.torpedo_damage);
+                health.last_hit = Some(team.0);
+            }
+        }
+    }
+}
+
+/// Defenders come out on a timer and whenever the base is hit, up to a limit.
+fn spawn_defenders(
+    time: GameTime,
+    rule: Res<SiegeRule>,
+    enemy_state: Res<State<EnemyState>>,
+    mut timer: ResMut<DefenderTimer>,
+    base: Query<(&Transform, &BaseHealth, ChangeTrackers<BaseHealth>), With<EnemyBase>>,
+    defenders: Query<(), With<Defender>>,
+    mut commands: Commands,
+) {
+    if !rule.enabled {
+        return;
+    }
+    let Ok((base_trans, health, tracker)) = base.get_single() else {
+        return;
+    };
+    let hit = tracker.is_changed() && !tracker.is_added();
+    let due = *enemy_state.current() == EnemyState::Released
+        && timer.0.tick(time.delta()).just_finished();
+    let count = defenders.iter().count();
+    if health.current == 0 || count >= rule.max_defenders || !(hit || due) {
+        return;
+    }
+    let angle = TAU * count as f32 / rule.max_defenders as f32;
+    let offset = DEFENDER_SPREAD * Vec3::new(angle.cos(), angle.sin(), 0.0);
+    let defender = spawn_enemy(&mut commands, base_trans.translation + offset);
+    commands.entity(defender).insert(Defender);
+}
+
+/// Destroying the base wins the game, except in survival, which can't be
+/// won; there it only pays the bonus.
+fn siege_victory(
+    rule: Res<SiegeRule>,
+    mode: Res<PlayerMode>,
+    game_mode: Res<GameMode>,
+    mut base: Query<&mut BaseHealth, With<EnemyBase>>,
+    mut score: ResMut<Score>,
+    mut winner: ResMut<Winner>,
+    mut game_state: ResMut<State<GameState>>,
+    mut gate: ResMut<EndGate>,
+) {
+    if !rule.enabled || winner.0.is_some() {
+        return;
+    }
+    let Ok(mut health) = base.get_single_mut() else {
+        return;
+    };
+    if health.current > 0 || health.fallen {
+        return;
+    }
+    let team = health
+        .last_hit
+        .filter(|team| *team < mode.teams())
+        .unwrap_or(0);
+    score.0[team] = score.0[team].saturating_add(rule.bonus as u32);
+    if *game_mode == GameMode::Survival {
+        health.fallen = true;
+    } else {
+        win(team, &mut winner, &mut game_state, &mut gate);
+    }
+}
@@ -1,5 +1,8 @@
 use crate::{CollisionFilters, CollisionMemberships};
-use crate::{EnemyBase, EnemyState, FoeStartingPoint, FriendStartingPoint, GameState, IsBase};
+use crate::{
+    BaseConfig, BaseHealth, BaseTurret, EnemyBase, FoeStartingPoint, FriendStartingPoint,
+    GameState, IsBase, PlayerMode, RivalStartingPoint, SiegeRule, Team, ENEMY_BASE_HALF_SIZE,
+};
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use bevy_rapier2d::geometry::Group;
@@ -10,48 +13,79 @@ pub struct StartPointPlugin;
 
 impl Plugin for StartPointPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
-            SystemSet::on_enter(EnemyState::Released).with_system(remove_enemy_base),
-        )
-        .add_system_set(SystemSet::on_enter(GameState::Game).with_system(spawn_startpoints))
-        .add_system_set(SystemSet::on_enter(GameState::Victory).with_system(despawn_startpoints))
-        .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(despawn_startpoints));
+        app.add_system_set(SystemSet::on_enter(GameState::Game).with_system(spawn_startpoints))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Victory).with_system(despawn_startpoints),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver).with_system(despawn_startpoints),
+            );
     }
 }
 
-fn remove_enemy_base(base: Query<Entity, With<EnemyBase>>, mut commands: Commands) {
-    let entity = base.single();
-    commands.entity(entity).despawn();
-}
-
-fn despawn_startpoints(mut commands: Commands, base: Query<Entity, With<IsBase>>) {
+fn despawn_startpoints(
+    mut commands: Commands,
+    base: Query<Entity, Or<(With<IsBase>, With<EnemyBase>)>>,
+) {
     for ent in base.iter() {
         commands.entity(ent).despawn();
     }
 }
 
+/// Where each team's base sits on the current map.
+pub fn team_start(team: usize, friend: &FriendStartingPoint, rival: &RivalStartingPoint) -> Vec3 {
+    match team {
+        0 => friend.0,
+        _ => rival.0,
+    }
+}
+
 fn spawn_startpoints(
     mut commands: Commands,
     friend_start: Res<FriendStartingPoint>,
+    rival_start: Res<RivalStartingPoint>,
     foe_start: Res<FoeStartingPoint>,
+    mode: Res<PlayerMode>,
+    siege: Res<SiegeRule>,
+    base_config: Res<BaseConfig>,
 ) {
-    commands
-        .spawn((
-        Collider::ball(START_RADIUS),
-        Sensor,
-        CollisionGroups::new(
-            Group::from_bits(CollisionMemberships::FriendlyBase as u32).unwrap(),
-            Group::from_bits(CollisionFilters::WithFriend as u32).unwrap(),
-        ),
-        IsBase,
-        TransformBundle::from_transform(
-            Transform::from_translation(friend_start.0),
-        )));
+    for team in 0..mode.teams() {
+        let base = commands
+            .spawn((
+            Collider::ball(START_RADIUS),
+            Sensor,
+            CollisionGroups::new(
+                Group::from_bits(CollisionMemberships::FriendlyBase as u32).unwrap(),
+                Group::from_bits(CollisionFilters::WithFriend as u32).unwrap(),
+            ),
+            IsBase,
+            Team(team),
+            TransformBundle::from_transform(
+                Transform::from_translation(team_start(team, &friend_start, &rival_start)),
+            )))
+            .id();
+        if base_config.turret {
+            commands.entity(base).insert(BaseTurret::new(&base_config));
+        }
+    }
 
-    commands
+    let enemy_base = commands
         .spawn((
         TransformBundle::from_transform(
             Transform::from_translation(foe_start.0),
         ),
-        EnemyBase));
+        EnemyBase))
+        .id();
+    if siege.enabled {
+        commands.entity(enemy_base).insert((
+            RigidBody::Fixed,
+            Collider::cuboid(ENEMY_BASE_HALF_SIZE, ENEMY_BASE_HALF_SIZE),
+            Sensor,
+            CollisionGroups::new(
+                Group::from_bits(CollisionMemberships::EnemyBase as u32).unwrap(),
+                Group::from_bits(CollisionFilters::EnemyBase as u32).unwrap(),
+            ),
+            BaseHealth::new(siege.health),
+        ));
+    }
 }
@@ -1,11 +1,14 @@
-use crate::GameSeed;
+use crate::{GameSeed, MAX_PLAYERS};
 use bevy::prelude::*;
-use std::cmp::max;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum AppState {
     MainMenu,
     SeedMenu,
+    SeedBrowser,
+    Shop,
+    Options,
+    Achievements,
     InGame,
 }
 
@@ -18,53 +21,85 @@ pub enum GameState {
     GameOver,
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct IsGlod;
 
 #[derive(Component)]
 pub struct Drawn;
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct IsBase;
 
-#[derive(Component)]
+/// Side an entity plays for; each team has its own base and score.
+#[derive(Component, Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Team(pub usize);
+
+#[derive(Component, Clone)]
 pub struct EnemyBase;
 
 #[derive(Resource)]
 pub struct FriendStartingPoint(pub Vec3);
 
 #[derive(Resource)]
-pub struct FoeStartingPoint(pub Vec3);
+pub struct RivalStartingPoint(pub Vec3);
 
 #[derive(Resource)]
-pub struct Score(pub u8);
+pub struct FoeStartingPoint(pub Vec3);
 
-#[derive(Resource)]
-pub struct HighScore(pub u8);
+/// Glod scored by each team; solo and single-team games only use the first.
+#[derive(Resource, Clone)]
+pub struct Score(pub [u32; MAX_PLAYERS]);
+
+impl Score {
+    pub fn total(&self) -> u32 {
+        self.0.iter().sum()
+    }
+
+    pub fn best(&self) -> u32 {
+        *self.0.iter().max().unwrap()
+    }
+}
+
+/// Team that won the last game, if any.
+#[derive(Resource, Clone)]
+pub struct Winner(pub Option<usize>);
+
+/// Holds back the end of an online game while the frame deciding it was
+/// simulated with guessed inputs; that frame is played again once they arrive.
+#[derive(Resource, Default)]
+pub struct EndGate {
+    /// Set while the frame being simulated relies on guessed inputs.
+    pub predicted: bool,
+    /// Set when such a frame would have ended the game.
+    pub held: bool,
+}
+
+impl EndGate {
+    /// Whether the game may end on this frame, noting that it wanted to if not.
+    pub fn allows_end(&mut self) -> bool {
+        self.held |= self.predicted;
+        !self.predicted
+    }
+}
 
 pub struct StatePlugin;
 
 impl Plugin for StatePlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(Score(0))
-            .insert_resource(HighScore(0))
+        app.insert_resource(Score([0; MAX_PLAYERS]))
+            .insert_resource(Winner(None))
+            .insert_resource(EndGate::default())
             .add_state(AppState::MainMenu)
             .add_state(GameState::OutOfGame)
             .add_system_set(SystemSet::on_update(GameState::OutOfGame).with_system(next_game))
             .add_system_set(SystemSet::on_update(GameState::SampleRandom).with_system(start_game))
-            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(reset_score))
-            .add_system_set(SystemSet::on_enter(GameState::Victory).with_system(update_high_score.label("score_update")));
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(reset_score));
     }
 }
 
-fn reset_score(mut score: ResMut<Score>) {
-    *score = match *score {
-        Score(_) => Score(0),
-    };
-}
-
-fn update_high_score(score: ResMut<Score>, mut high_score: ResMut<HighScore>) {
-    *high_score = HighScore(max(score.0, high_score.0));
+fn reset_score(mut score: ResMut<Score>, mut winner: ResMut<Winner>) {
+    *score = Score([0; MAX_PLAYERS]);
+    *winner = Winner(None);
 }
 
 fn start_game(mut game_state: ResMut<State<GameState>>) {
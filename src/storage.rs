@@ -0,0 +1,52 @@
+//! Small key-value persistence: browser local storage on wasm, files on native.
+
+#[cfg(target_family = "wasm")]
+pub fn load(key: &str) -> Option<String> {
+    let local_storage = web_sys::window().unwrap().local_storage().unwrap().unwrap();
+    local_storage.get_item(key).unwrap()
+}
+
+#[cfg(target_family = "wasm")]
+pub fn save(key: &str, value: &str) {
+    let local_storage = web_sys::window().unwrap().local_storage().unwrap().unwrap();
+    local_storage.set_item(key, value).unwrap();
+}
+
+/// The file holding `key`, unless the key would lead out of the save directory.
+#[cfg(not(target_family = "wasm"))]
+fn path(key: &str) -> Option<std::path::PathBuf> {
+    if key.is_empty() || key == ".." || key.chars().any(std::path::is_separator) {
+        return None;
+    }
+    let home = std::env::var_os("HOME").unwrap_or_else(|| ".".into());
+    Some(std::path::Path::new(&home).join(".glodget").join(key))
+}
+
+#[cfg(not(target_family = "wasm"))]
+pub fn load(key: &str) -> Option<String> {
+    std::fs::read_to_string(path(key)?).ok()
+}
+
+#[cfg(not(target_family = "wasm"))]
+pub fn save(key: &str, value: &str) {
+    let Some(path) = path(key) else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let _ = std::fs::write(path, value);
+}
+
+#[cfg(all(test, not(target_family = "wasm")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_stays_in_the_save_directory() {
+        assert!(path("ghost_classic_abc-1").is_some());
+        assert!(path("ghost_classic_../../.bashrc").is_none());
+        assert!(path("..").is_none());
+        assert!(path("").is_none());
+    }
+}
@@ -0,0 +1,170 @@
+//! A guided first game on a hand-made map, one prompt at a time.
+//!
+//! The tutorial swaps in its own level, mode and rules while it runs, and puts
+//! the player's own settings back once they leave the game.
+use crate::{
+    AppState, Cargo, GameMode, GameSeed, GameState, Level, Playable, PlayerMode, ReleaseRule,
+    ReleaseTrigger, RuleSet, Rules, ShipInput, Team, Torpedo,
+};
+use bevy::prelude::*;
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Tutorial::default())
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(restart_tutorial))
+            .add_system_set(
+                SystemSet::on_update(GameState::Game)
+                    .with_system(advance_tutorial.after("inputs").after("release")),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::InGame).with_system(end_tutorial));
+    }
+}
+
+/// The tutorial map: glod in a line leading away from the base, and a
+/// torpedo refill halfway along.
+const TUTORIAL_LEVEL: &str = "
+friend -200 -200
+foe 200 200
+glod -120 -200
+glod -60 -200
+glod 0 -200
+glod 60 -200
+glod 120 -200
+torpedoes 0 -140
+";
+const TUTORIAL_SEED: &str = "tutorial";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TutorialStep {
+    Thrust,
+    Turn,
+    CollectGlod,
+    Fire,
+    ReturnToBase,
+}
+
+impl TutorialStep {
+    pub fn prompt(&self) -> &'static str {
+        match self {
+            TutorialStep::Thrust => {
+                "Hold Up to thrust out of your base.\nLeaving it wakes the enemy!"
+            }
+            TutorialStep::Turn => "Left and Right turn your ship",
+            TutorialStep::CollectGlod => "Fly into the glod to collect it",
+            TutorialStep::Fire => "The enemy is loose!\nSpace fires a torpedo to knock it away",
+            TutorialStep::ReturnToBase => "Now fly back to your base to win",
+        }
+    }
+}
+
+/// Settings the tutorial replaces, to be put back afterwards.
+#[derive(Clone, Debug)]
+struct SavedSettings {
+    seed: GameSeed,
+    game_mode: GameMode,
+    player_mode: PlayerMode,
+    rules: RuleSet,
+}
+
+#[derive(Resource, Default)]
+pub struct Tutorial {
+    /// Step the player is on, while the tutorial is running.
+    pub step: Option<TutorialStep>,
+    saved: Option<SavedSettings>,
+}
+
+impl Tutorial {
+    pub fn active(&self) -> bool {
+        self.saved.is_some()
+    }
+
+    pub fn level() -> Level {
+        Level::parse(TUTORIAL_LEVEL).expect("tutorial level is valid")
+    }
+
+    /// Sets up a solo classic game on the tutorial map, with every optional rule off.
+    pub fn start(
+        &mut self,
+        seed: &mut GameSeed,
+        game_mode: &mut GameMode,
+        player_mode: &mut PlayerMode,
+        rules: &mut Rules,
+    ) {
+        if !self.active() {
+            self.saved = Some(SavedSettings {
+                seed: seed.clone(),
+                game_mode: *game_mode,
+                player_mode: *player_mode,
+                rules: rules.get(),
+            });
+        }
+        *seed = GameSeed(TUTORIAL_SEED.to_string());
+        *game_mode = GameMode::Classic;
+        *player_mode = PlayerMode::Solo;
+        *rules.release = ReleaseRule {
+            trigger: ReleaseTrigger::LeaveBase,
+            stages: 0,
+            ..*rules.release
+        };
+        rules.deposit.enabled = false;
+        rules.siege.enabled = false;
+        rules.base.turret = false;
+        rules.director.enabled = false;
+        rules.enemy_fire.enabled = false;
+        rules.asteroids.enabled = false;
+        rules.hazards.enabled = false;
+        rules.fog.enabled = false;
+        self.step = Some(TutorialStep::Thrust);
+    }
+}
+
+fn restart_tutorial(mut tutorial: ResMut<Tutorial>) {
+    if tutorial.active() {
+        tutorial.step = Some(TutorialStep::Thrust);
+    }
+}
+
+fn advance_tutorial(
+    mut tutorial: ResMut<Tutorial>,
+    ships: Query<(&ShipInput, &Cargo), With<Playable>>,
+    torpedoes: Query<(), (Added<Torpedo>, With<Team>)>,
+) {
+    let Some(step) = tutorial.step else {
+        return;
+    };
+    let done = match step {
+        TutorialStep::Thrust => ships.iter().any(|(input, _)| input.thrust),
+        TutorialStep::Turn => ships.iter().any(|(input, _)| input.left || input.right),
+        TutorialStep::CollectGlod => ships.iter().any(|(_, cargo)| !cargo.0.is_empty()),
+        TutorialStep::Fire => !torpedoes.is_empty(),
+        TutorialStep::ReturnToBase => false,
+    };
+    if !done {
+        return;
+    }
+    tutorial.step = Some(match step {
+        TutorialStep::Thrust => TutorialStep::Turn,
+        TutorialStep::Turn => TutorialStep::CollectGlod,
+        TutorialStep::CollectGlod => TutorialStep::Fire,
+        TutorialStep::Fire | TutorialStep::ReturnToBase => TutorialStep::ReturnToBase,
+    });
+}
+
+fn end_tutorial(
+    mut tutorial: ResMut<Tutorial>,
+    mut seed: ResMut<GameSeed>,
+    mut game_mode: ResMut<GameMode>,
+    mut player_mode: ResMut<PlayerMode>,
+    mut rules: Rules,
+) {
+    let Some(saved) = tutorial.saved.take() else {
+        return;
+    };
+    *seed = saved.seed;
+    *game_mode = saved.game_mode;
+    *player_mode = saved.player_mode;
+    rules.set(&saved.rules);
+    tutorial.step = None;
+}
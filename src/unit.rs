@@ -1,8 +1,10 @@
 use crate::{BodyForce, ForceProfile};
 use crate::{CollisionFilters, CollisionMemberships};
 use crate::{
-    Explodee, FoeStartingPoint, FriendStartingPoint, GameState, IsBase, IsGlod, Score, Weapon,
-    WeaponPreLaunch, GLOD_MASS, WEAPON_MASS,
+    ship_collision_groups, team_start, DepositRule, Difficulty, Dormant, EndGate, EnemyOrdnance,
+    Explodee, FoeStartingPoint, FriendStartingPoint, GameState, GlodTier, IsBase, IsGlod,
+    PlayerMode, ReleaseRule, RivalStartingPoint, Score, Shielded, Team, Upgrades, Weapon,
+    WeaponPreLaunch, Winner, WEAPON_MASS,
 };
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
@@ -13,8 +15,14 @@ pub struct UnitPlugin;
 impl Plugin for UnitPlugin {
     fn build(&self, app: &mut App) {
         app.add_state(EnemyState::Undefined)
+            .add_event::<ShipLost>()
+            .add_event::<BaseReached>()
             .add_system_set(SystemSet::on_enter(GameState::Game).with_system(spawn_unit))
-            .add_system_set(SystemSet::on_update(GameState::Game).with_system(display_events))
+            .add_system_set(
+                SystemSet::on_update(GameState::Game)
+                    .with_system(display_events.label("ship_events"))
+                    .with_system(resolve_ship_lost.after("ship_events").after("victory")),
+            )
             .add_system_set(SystemSet::on_enter(GameState::Victory).with_system(despawn_units))
             .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(despawn_units));
     }
@@ -25,8 +33,12 @@ fn despawn_units(
     unit: Query<Entity, With<Playable>>,
     enemy: Query<Entity, With<IsEnemy>>,
 ) {
-    commands.entity(unit.single()).despawn_recursive();
-    commands.entity(enemy.single()).despawn();
+    for unit in unit.iter() {
+        commands.entity(unit).despawn_recursive();
+    }
+    for enemy in enemy.iter() {
+        commands.entity(enemy).despawn();
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
@@ -36,17 +48,85 @@ pub enum EnemyState {
     Released,
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Playable;
 
-#[derive(Component)]
+/// Which local player flies a ship, selecting its controls.
+#[derive(Component, Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Pilot(pub usize);
+
+#[derive(Component, Clone, Copy)]
+pub struct ShipColour(pub Color);
+
+pub const SHIP_COLOURS: [Color; 2] = [Color::BLUE, Color::GREEN];
+
+/// Sent when a ship is destroyed by the enemy or by leaving the arena.
+pub struct ShipLost(pub Entity);
+
+/// Sent when a ship touches its own team's base, after any cargo is banked.
+pub struct BaseReached(pub usize);
+
+#[derive(Component, Clone)]
 pub struct IsEnemy;
 
+/// Glod currently carried by a ship, weighing it down.
+#[derive(Component, Clone, Default)]
+pub struct Cargo(pub Vec<GlodTier>);
+
+impl Cargo {
+    /// Points the carried glod is worth.
+    pub fn value(&self) -> u32 {
+        self.0.iter().map(|tier| tier.value() as u32).sum()
+    }
+
+    /// Weight of the carried glod, in common glod.
+    pub fn weight(&self) -> u32 {
+        self.0.iter().map(|tier| tier.weight() as u32).sum()
+    }
+}
+
+/// Controls applied to a ship this frame, from the keyboard or from the network.
+#[derive(Component, Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ShipInput {
+    pub left: bool,
+    pub right: bool,
+    pub thrust: bool,
+    pub fire: bool,
+    pub jettison: bool,
+    pub ping: bool,
+}
+
+impl ShipInput {
+    pub fn to_bits(&self) -> u8 {
+        self.left as u8
+            | (self.right as u8) << 1
+            | (self.thrust as u8) << 2
+            | (self.fire as u8) << 3
+            | (self.jettison as u8) << 4
+            | (self.ping as u8) << 5
+    }
+
+    pub fn from_bits(bits: u8) -> ShipInput {
+        ShipInput {
+            left: bits & 1 != 0,
+            right: bits & 1 << 1 != 0,
+            thrust: bits & 1 << 2 != 0,
+            fire: bits & 1 << 3 != 0,
+            jettison: bits & 1 << 4 != 0,
+            ping: bits & 1 << 5 != 0,
+        }
+    }
+}
+
+/// A ship whose `ShipInput` arrives over the network rather than from the keyboard.
+#[derive(Component, Clone)]
+pub struct RemoteControlled;
+
 pub const UNIT_FORCE_PROFILE: ForceProfile = ForceProfile {
     forward: 150.0,
     torque: 3000.0,
 };
-const UNIT_MASS: f32 = 3.0;
+pub const UNIT_MASS: f32 = 3.0;
 
 pub const UNIT_POINTS: [Vec2; 3] = [
     Vec2::new(-8.0, -8.0),
@@ -54,21 +134,77 @@ pub const UNIT_POINTS: [Vec2; 3] = [
     Vec2::new(14.0, 0.0),
 ];
 
-#[derive(Component)]
-struct PlayableCollider;
+#[derive(Component, Clone)]
+pub struct PlayableCollider;
+
+/// Torpedoes are mounted in pairs either side of the hull, working backwards.
+fn weapon_slot(slot: usize) -> Transform {
+    let side = if slot % 2 == 0 { 10.0 } else { -10.0 };
+    Transform::from_xyz(4.0 - 7.0 * (slot / 2) as f32, side, 0.0)
+}
+
+/// Offset between ships sharing a base, so they don't spawn on top of each other.
+const SHARED_BASE_SPACING: f32 = 24.0;
+
+/// Offset between enemies released from the same enemy base.
+const ENEMY_SPACING: f32 = 30.0;
 
 fn spawn_unit(
     mut commands: Commands,
     friend_start: Res<FriendStartingPoint>,
+    rival_start: Res<RivalStartingPoint>,
     foe_start: Res<FoeStartingPoint>,
+    upgrades: Res<Upgrades>,
+    mode: Res<PlayerMode>,
+    release: Res<ReleaseRule>,
     mut enemy_state: ResMut<State<EnemyState>>,
 ) {
+    let shared_base = mode.players() > mode.teams();
+    for pilot in 0..mode.players() {
+        let team = mode.team_of(pilot);
+        let mut pos = team_start(team, &friend_start, &rival_start);
+        if shared_base {
+            pos.x += SHARED_BASE_SPACING * (pilot as f32 - 0.5);
+        }
+        spawn_ship(&mut commands, pilot, SHIP_COLOURS[pilot], Team(team), pos, &upgrades);
+    }
+
+    enemy_state.set(EnemyState::PreStart).unwrap();
+
+    // Staged enemies follow the first ones out, one every stage.
+    let enemies = mode.enemies() + release.stages;
+    for i in 0..enemies {
+        let offset = ENEMY_SPACING * (i as f32 - (enemies - 1) as f32 / 2.0);
+        let enemy = spawn_enemy(&mut commands, foe_start.0 + Vec3::new(offset, 0.0, 0.0));
+        if i >= mode.enemies() {
+            let wait = release.stage_seconds * (i + 1 - mode.enemies()) as f32;
+            commands
+                .entity(enemy)
+                .insert(Dormant(Timer::from_seconds(wait, TimerMode::Once)));
+        }
+    }
+}
+
+/// Spawns a player ship with its collider and torpedo stock as children.
+pub fn spawn_ship(
+    commands: &mut Commands,
+    pilot: usize,
+    colour: Color,
+    team: Team,
+    pos: Vec3,
+    upgrades: &Upgrades,
+) -> Entity {
     commands
         .spawn((
         TransformBundle::from_transform(
-            Transform::from_translation(friend_start.0),
+            Transform::from_translation(pos),
         ),
         Playable,
+        Pilot(pilot),
+        ShipColour(colour),
+        team,
+        Cargo::default(),
+        ShipInput::default(),
         Explodee,
         RigidBody::Dynamic))
         .with_children(|children| {
@@ -83,42 +219,11 @@ fn spawn_unit(
                     ),
                     ActiveEvents::COLLISION_EVENTS,
                     ColliderMassProperties::Mass(UNIT_MASS),
-                    CollisionGroups::new(
-                        Group::from_bits(CollisionMemberships::Friend as u32).unwrap(),
-                        Group::from_bits(CollisionFilters::Friend as u32).unwrap(),
-                )));
-            children
-                .spawn((
-                Collider::cuboid(5.0, 3.0),
-                ColliderMassProperties::Mass(WEAPON_MASS),
-                CollisionGroups::new(
-                    Group::from_bits(CollisionMemberships::InertWeapon as u32).unwrap(),
-                    Group::from_bits(CollisionFilters::InertWeapon as u32).unwrap(),
-                ),
-                TransformBundle::from_transform(Transform::from_xyz(
-                    4.0, 10.0, 0.0,
-                )),
-                Weapon,
-                WeaponPreLaunch));
-            children
-                .spawn((
-                Collider::cuboid(5.0, 3.0),
-                ColliderMassProperties::Mass(WEAPON_MASS),
-                CollisionGroups::new(
-                    Group::from_bits(CollisionMemberships::InertWeapon as u32).unwrap(),
-                    Group::from_bits(CollisionFilters::InertWeapon as u32).unwrap(),
-                ),
-                TransformBundle::from_transform(Transform::from_xyz(
-                    4.0, -10.0, 0.0,
-                )),
-                Weapon,
-                WeaponPreLaunch));
+                    ship_collision_groups(team.0)));
+            spawn_torpedo_stock(children, upgrades);
         })
         .insert(Velocity::zero())
-        .insert(Damping {
-            linear_damping: 0.4,
-            angular_damping: 1.0,
-        })
+        .insert(upgrades.damping())
         .insert(ExternalForce {
             force: Vec2::ZERO,
             torque: 0.0,
@@ -126,14 +231,32 @@ fn spawn_unit(
         .insert(ExternalImpulse {
             impulse: Vec2::ZERO,
             torque_impulse: 0.0,
-        });
+        })
+        .id()
+}
 
-    enemy_state.set(EnemyState::PreStart).unwrap();
+/// Mounts a full load of torpedoes on a ship.
+pub fn spawn_torpedo_stock(children: &mut ChildBuilder, upgrades: &Upgrades) {
+    for slot in 0..upgrades.torpedo_count() {
+        children
+            .spawn((
+            Collider::cuboid(5.0, 3.0),
+            ColliderMassProperties::Mass(WEAPON_MASS),
+            CollisionGroups::new(
+                Group::from_bits(CollisionMemberships::InertWeapon as u32).unwrap(),
+                Group::from_bits(CollisionFilters::InertWeapon as u32).unwrap(),
+            ),
+            TransformBundle::from_transform(weapon_slot(slot)),
+            Weapon,
+            WeaponPreLaunch));
+    }
+}
 
+pub fn spawn_enemy(commands: &mut Commands, pos: Vec3) -> Entity {
     commands
         .spawn((
         TransformBundle::from_transform(
-            Transform::from_translation(foe_start.0),
+            Transform::from_translation(pos),
         ),
         IsEnemy,
         RigidBody::Dynamic,
@@ -162,22 +285,30 @@ fn spawn_unit(
         ExternalImpulse {
             impulse: Vec2::ZERO,
             torque_impulse: 0.0,
-        }));
+        }))
+        .id()
 }
 
 fn display_events(
-    mut query: Query<(Entity, &mut ColliderMassProperties), With<PlayableCollider>>,
-    glods: Query<Entity, With<IsGlod>>,
+    mut query: Query<(Entity, &Parent, &mut ColliderMassProperties), With<PlayableCollider>>,
+    mut ships: Query<(&mut Cargo, &Team, Option<&Shielded>)>,
+    difficulty: Res<Difficulty>,
+    deposit: Res<DepositRule>,
+    glods: Query<&GlodTier, With<IsGlod>>,
     enemy: Query<Entity, With<IsEnemy>>,
-    base: Query<Entity, With<IsBase>>,
-    mut game_state: ResMut<State<GameState>>,
-    mut enemy_state: ResMut<State<EnemyState>>,
+    ordnance: Query<(), With<EnemyOrdnance>>,
+    base: Query<&Team, With<IsBase>>,
     mut score: ResMut<Score>,
+    mut lost: EventWriter<ShipLost>,
+    mut reached: EventWriter<BaseReached>,
     mut collision_events: EventReader<CollisionEvent>,
     mut commands: Commands,
 ) {
     for collision_event in collision_events.iter() {
-        for (entity, mut props) in query.iter_mut() {
+        for (entity, ship, mut props) in query.iter_mut() {
+            let Ok((mut cargo, team, shield)) = ships.get_mut(ship.get()) else {
+                continue;
+            };
             if let CollisionEvent::Started(h1, h2, _event_flag) = collision_event {
                 let notme: &Entity;
                 if *h1 == entity {
@@ -187,50 +318,178 @@ fn display_events(
                 } else {
                     continue;
                 }
-                if glods.contains(*notme) {
+                if let Ok(tier) = glods.get(*notme) {
                     commands.entity(*notme).despawn();
-                    *score = match *score {
-                        Score(x) => Score(x + 1),
-                    };
-                    match *props {
-                        ColliderMassProperties::Mass(cur) => {
-                            *props = ColliderMassProperties::Mass(GLOD_MASS + cur);
-                        }
-                        _ => panic!(),
+                    if !deposit.enabled {
+                        score.0[team.0] = score.0[team.0].saturating_add(tier.value() as u32);
                     }
-                } else if enemy.contains(*notme) {
-                    game_state.set(GameState::GameOver).unwrap();
-                } else if base.contains(*notme) {
-                    match enemy_state.current() {
-                        EnemyState::Released => {
-                            game_state.set(GameState::Victory).unwrap();
-                        }
-                        _ => continue,
-                    };
-                }
-            } else if let CollisionEvent::Stopped(h1, h2, _event_flag) = collision_event {
-                let notme: &Entity;
-                if *h1 == entity {
-                    notme = h2;
-                } else if *h2 == entity {
-                    notme = h1;
-                } else {
-                    continue;
-                }
-
-                if base.contains(*notme) {
-                    enemy_state.set(EnemyState::Released).unwrap();
+                    cargo.0.push(*tier);
+                    *props = ColliderMassProperties::Mass(
+                        UNIT_MASS + difficulty.cargo_mass(cargo.weight()),
+                    );
+                } else if enemy.contains(*notme) || ordnance.contains(*notme) {
+                    if shield.is_none() {
+                        lost.send(ShipLost(ship.get()));
+                    }
+                } else if base.get(*notme) == Ok(team) {
+                    if deposit.enabled {
+                        score.0[team.0] = score.0[team.0].saturating_add(cargo.value());
+                        cargo.0.clear();
+                        *props = ColliderMassProperties::Mass(UNIT_MASS);
+                    }
+                    reached.send(BaseReached(team.0));
                 }
             }
         }
     }
 }
 
-pub fn unit_movement_order<T: Component>(
-    order: BodyForce,
-    mut query: Query<(&Transform, &mut ExternalForce), With<T>>,
+fn resolve_ship_lost(
+    mut lost: EventReader<ShipLost>,
+    ships: Query<(Entity, &Team), With<Playable>>,
+    mode: Res<PlayerMode>,
+    mut winner: ResMut<Winner>,
+    mut game_state: ResMut<State<GameState>>,
+    mut gate: ResMut<EndGate>,
+    mut commands: Commands,
 ) {
-    let (transform, mut ext_force) = query.single_mut();
-    ext_force.torque = order.torque;
-    ext_force.force = order.force_from_transform(transform);
+    let lost: Vec<Entity> = lost.iter().map(|ShipLost(ship)| *ship).collect();
+    if lost.is_empty() || winner.0.is_some() {
+        return;
+    }
+    let survivors: Vec<&Team> = ships
+        .iter()
+        .filter(|(ship, _)| !lost.contains(ship))
+        .map(|(_, team)| team)
+        .collect();
+    let ends = survivors.is_empty() || *mode == PlayerMode::Versus;
+    if ends && !gate.allows_end() {
+        return;
+    }
+
+    match (*mode, survivors.first()) {
+        (_, None) => game_state.set(GameState::GameOver).unwrap(),
+        (PlayerMode::Versus, Some(team)) => {
+            *winner = Winner(Some(team.0));
+            game_state.set(GameState::Victory).unwrap();
+        }
+        _ => {
+            for ship in lost {
+                commands.entity(ship).despawn_recursive();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_rapier2d::rapier::geometry::CollisionEventFlags;
+
+    /// A deposit game with one ship, returning the ship's collider.
+    fn deposit_game() -> (App, Entity) {
+        let mut app = App::new();
+        app.add_state(GameState::Game)
+            .add_event::<CollisionEvent>()
+            .add_event::<ShipLost>()
+            .add_event::<BaseReached>()
+            .insert_resource(Difficulty::Normal)
+            .insert_resource(PlayerMode::Solo)
+            .insert_resource(DepositRule {
+                enabled: true,
+                ..default()
+            })
+            .insert_resource(Score([0, 0]))
+            .insert_resource(Winner(None))
+            .insert_resource(EndGate::default())
+            .add_system(display_events.label("ship_events"))
+            .add_system(resolve_ship_lost.after("ship_events"));
+        let ship = app.world.spawn((Playable, Cargo::default(), Team(0))).id();
+        let collider = app
+            .world
+            .spawn((PlayableCollider, ColliderMassProperties::Mass(UNIT_MASS)))
+            .id();
+        app.world.entity_mut(ship).push_children(&[collider]);
+        (app, collider)
+    }
+
+    /// Flies the ship into a new entity with `bundle`, as rapier reports it.
+    fn touch(app: &mut App, collider: Entity, bundle: impl Bundle) {
+        let other = app.world.spawn(bundle).id();
+        let event = CollisionEvent::Started(collider, other, CollisionEventFlags::empty());
+        app.world
+            .resource_mut::<Events<CollisionEvent>>()
+            .send(event);
+        app.update();
+    }
+
+    #[test]
+    fn glod_only_scores_once_deposited() {
+        let (mut app, collider) = deposit_game();
+        touch(&mut app, collider, (IsGlod, GlodTier::Common));
+        touch(&mut app, collider, (IsGlod, GlodTier::Common));
+        assert_eq!(app.world.resource::<Score>().0[0], 0);
+        touch(&mut app, collider, (IsBase, Team(0)));
+        assert_eq!(app.world.resource::<Score>().0[0], 2);
+        let cargo = app.world.query::<&Cargo>().single(&app.world);
+        assert!(cargo.0.is_empty());
+    }
+
+    #[test]
+    fn deposited_glod_is_kept_when_the_ship_is_lost() {
+        let (mut app, collider) = deposit_game();
+        touch(&mut app, collider, (IsGlod, GlodTier::Common));
+        touch(&mut app, collider, (IsBase, Team(0)));
+        touch(&mut app, collider, (IsGlod, GlodTier::Common));
+        touch(&mut app, collider, IsEnemy);
+        app.update();
+        assert_eq!(app.world.resource::<Score>().0[0], 1);
+        assert_eq!(
+            *app.world.resource::<State<GameState>>().current(),
+            GameState::GameOver
+        );
+    }
+
+    #[test]
+    fn losing_on_guessed_inputs_waits_for_the_real_ones() {
+        let (mut app, collider) = deposit_game();
+        app.world.resource_mut::<EndGate>().predicted = true;
+        touch(&mut app, collider, IsEnemy);
+        app.update();
+        assert!(app.world.resource::<EndGate>().held);
+        assert_eq!(
+            *app.world.resource::<State<GameState>>().current(),
+            GameState::Game
+        );
+
+        app.world.resource_mut::<EndGate>().predicted = false;
+        touch(&mut app, collider, IsEnemy);
+        app.update();
+        assert_eq!(
+            *app.world.resource::<State<GameState>>().current(),
+            GameState::GameOver
+        );
+    }
+
+    #[test]
+    fn ship_input_bits_round_trip() {
+        for bits in 0..64 {
+            assert_eq!(ShipInput::from_bits(bits).to_bits(), bits);
+        }
+    }
+
+    #[test]
+    fn each_control_has_its_own_bit() {
+        let thrust = ShipInput {
+            thrust: true,
+            ..default()
+        };
+        let ping = ShipInput {
+            ping: true,
+            ..default()
+        };
+        assert_eq!(thrust.to_bits(), 0b100);
+        assert_eq!(ping.to_bits(), 0b100000);
+        assert!(ShipInput::from_bits(0b1000).fire);
+    }
 }
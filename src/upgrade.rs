@@ -0,0 +1,245 @@
+use crate::{counts, Cli, DepositRule, Explosion, ForceProfile, GameState, Score, Tutorial};
+use crate::storage;
+use crate::{TORPEDO_EXPLOSION, TORPEDO_FUSE, UNIT_FORCE_PROFILE};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use std::time::Duration;
+
+pub struct UpgradePlugin;
+
+impl Plugin for UpgradePlugin {
+    fn build(&self, app: &mut App) {
+        let wallet: u32 = storage::load("wallet")
+            .unwrap_or("0".to_string())
+            .parse::<u32>()
+            .unwrap_or(0);
+        let upgrades = Upgrades::from_storage(&storage::load("upgrades").unwrap_or_default());
+        app.insert_resource(Wallet(wallet))
+            .insert_resource(upgrades)
+            .add_system_set(SystemSet::on_enter(GameState::Victory).with_system(bank_score))
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver).with_system(bank_deposited_score),
+            )
+            .add_system(save_upgrades);
+    }
+}
+
+/// Glod carried over between rounds, spent in the upgrade shop.
+#[derive(Resource)]
+pub struct Wallet(pub u32);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Upgrade {
+    Thrust,
+    Torque,
+    Damping,
+    Torpedoes,
+    Fuse,
+    BlastRadius,
+    BlastImpulse,
+}
+
+pub const UPGRADES: [Upgrade; 7] = [
+    Upgrade::Thrust,
+    Upgrade::Torque,
+    Upgrade::Damping,
+    Upgrade::Torpedoes,
+    Upgrade::Fuse,
+    Upgrade::BlastRadius,
+    Upgrade::BlastImpulse,
+];
+
+pub const UPGRADE_MAX_LEVEL: u8 = 5;
+
+impl Upgrade {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Upgrade::Thrust => "Thrust",
+            Upgrade::Torque => "Torque",
+            Upgrade::Damping => "Drag reduction",
+            Upgrade::Torpedoes => "Torpedoes",
+            Upgrade::Fuse => "Fuse length",
+            Upgrade::BlastRadius => "Blast radius",
+            Upgrade::BlastImpulse => "Blast impulse",
+        }
+    }
+
+    fn base_cost(&self) -> u32 {
+        match self {
+            Upgrade::Torpedoes => 10,
+            Upgrade::BlastImpulse | Upgrade::BlastRadius => 6,
+            _ => 4,
+        }
+    }
+
+    /// Price of going from `level` to `level + 1`.
+    pub fn cost(&self, level: u8) -> u32 {
+        self.base_cost() * (level as u32 + 1)
+    }
+}
+
+#[derive(Resource, Default, Clone, PartialEq, Debug)]
+pub struct Upgrades {
+    levels: [u8; UPGRADES.len()],
+}
+
+impl Upgrades {
+    pub fn from_storage(stored: &str) -> Upgrades {
+        let mut upgrades = Upgrades::default();
+        for (level, stored) in upgrades.levels.iter_mut().zip(stored.split(',')) {
+            *level = stored.parse::<u8>().unwrap_or(0).min(UPGRADE_MAX_LEVEL);
+        }
+        upgrades
+    }
+
+    pub fn to_storage(&self) -> String {
+        self.levels
+            .iter()
+            .map(|level| level.to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    pub fn level(&self, upgrade: Upgrade) -> u8 {
+        self.levels[upgrade as usize]
+    }
+
+    /// Buys the next level of `upgrade` if it is affordable and not maxed out.
+    pub fn buy(&mut self, upgrade: Upgrade, wallet: &mut Wallet) -> bool {
+        let level = self.level(upgrade);
+        let cost = upgrade.cost(level);
+        if level >= UPGRADE_MAX_LEVEL || wallet.0 < cost {
+            return false;
+        }
+        wallet.0 -= cost;
+        self.levels[upgrade as usize] += 1;
+        true
+    }
+
+    fn scale(&self, upgrade: Upgrade, step: f32) -> f32 {
+        1.0 + step * self.level(upgrade) as f32
+    }
+
+    pub fn force_profile(&self) -> ForceProfile {
+        ForceProfile {
+            forward: UNIT_FORCE_PROFILE.forward * self.scale(Upgrade::Thrust, 0.1),
+            torque: UNIT_FORCE_PROFILE.torque * self.scale(Upgrade::Torque, 0.1),
+        }
+    }
+
+    /// Each level of the upgrade cuts linear drag, so the ship holds its speed.
+    pub fn damping(&self) -> Damping {
+        Damping {
+            linear_damping: 0.4 / self.scale(Upgrade::Damping, 0.2),
+            angular_damping: 1.0,
+        }
+    }
+
+    pub fn torpedo_count(&self) -> usize {
+        2 + self.level(Upgrade::Torpedoes) as usize
+    }
+
+    pub fn fuse(&self) -> Duration {
+        TORPEDO_FUSE.mul_f32(self.scale(Upgrade::Fuse, 0.15))
+    }
+
+    pub fn explosion(&self) -> Explosion {
+        Explosion {
+            radius: TORPEDO_EXPLOSION.radius * self.scale(Upgrade::BlastRadius, 0.1),
+            origin_impulse: TORPEDO_EXPLOSION.origin_impulse
+                * self.scale(Upgrade::BlastImpulse, 0.15),
+        }
+    }
+}
+
+fn bank_score(
+    cli: Res<Cli>,
+    tutorial: Res<Tutorial>,
+    score: Res<Score>,
+    mut wallet: ResMut<Wallet>,
+) {
+    if counts(&cli, &tutorial) {
+        wallet.0 += score.total();
+    }
+}
+
+/// Glod deposited at base is safe even if the ship is lost afterwards.
+fn bank_deposited_score(
+    cli: Res<Cli>,
+    tutorial: Res<Tutorial>,
+    score: Res<Score>,
+    deposit: Res<DepositRule>,
+    mut wallet: ResMut<Wallet>,
+) {
+    if deposit.enabled && counts(&cli, &tutorial) {
+        wallet.0 += score.total();
+    }
+}
+
+/// A replay's upgrades are only borrowed for playback, so they are never saved.
+fn save_upgrades(cli: Res<Cli>, wallet: Res<Wallet>, upgrades: Res<Upgrades>) {
+    if cli.replay.is_some() || (!wallet.is_changed() && !upgrades.is_changed()) {
+        return;
+    }
+    storage::save("wallet", &wallet.0.to_string());
+    storage::save("upgrades", &upgrades.to_storage());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The wallet after a game that banked 4 glod ends with the ship lost.
+    fn wallet_after_game_over(cli: Cli, deposit: DepositRule) -> u32 {
+        let mut app = App::new();
+        app.add_state(GameState::Game)
+            .insert_resource(cli)
+            .insert_resource(Tutorial::default())
+            .insert_resource(deposit)
+            .insert_resource(Score([3, 1]))
+            .insert_resource(Wallet(10))
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver).with_system(bank_deposited_score),
+            );
+        app.update();
+        app.world
+            .resource_mut::<State<GameState>>()
+            .set(GameState::GameOver)
+            .unwrap();
+        app.update();
+        app.world.resource::<Wallet>().0
+    }
+
+    #[test]
+    fn deposited_glod_is_banked_on_game_over() {
+        let deposit = DepositRule {
+            enabled: true,
+            ..default()
+        };
+        assert_eq!(wallet_after_game_over(Cli::default(), deposit), 14);
+    }
+
+    #[test]
+    fn carried_glod_is_lost_on_game_over() {
+        let deposit = DepositRule::default();
+        assert_eq!(wallet_after_game_over(Cli::default(), deposit), 10);
+    }
+
+    #[test]
+    fn replays_and_levels_bank_nothing() {
+        let deposit = DepositRule {
+            enabled: true,
+            ..default()
+        };
+        let replay = Cli {
+            replay: Some("run.txt".into()),
+            ..default()
+        };
+        let level = Cli {
+            level: Some("maze.txt".into()),
+            ..default()
+        };
+        assert_eq!(wallet_after_game_over(replay, deposit), 10);
+        assert_eq!(wallet_after_game_over(level, deposit), 10);
+    }
+}
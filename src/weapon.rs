@@ -1,5 +1,7 @@
-use crate::{Body, BodyForce, Drawn, ForceProfile, GameState};
+use crate::{Body, BodyForce, CollisionFilters, CollisionMemberships, Drawn, ForceProfile};
+use crate::{GameState, GameTime, Team};
 use bevy::prelude::*;
+use bevy_rapier2d::geometry::Group;
 use bevy_rapier2d::prelude::*;
 use std::time::Duration;
 
@@ -25,13 +27,13 @@ fn despawn_weapons(mut commands: Commands, weapons: Query<Entity, With<Weapon>>)
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Weapon;
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct WeaponPreLaunch;
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct WeaponLaunch;
 
 const WEAPON_FORCE_PROFILE: ForceProfile = ForceProfile {
@@ -40,13 +42,19 @@ const WEAPON_FORCE_PROFILE: ForceProfile = ForceProfile {
 };
 pub const WEAPON_MASS: f32 = 0.1;
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Explodee;
 
-#[derive(Component)]
+pub const TORPEDO_FUSE: Duration = Duration::from_millis(2500);
+pub const TORPEDO_EXPLOSION: Explosion = Explosion {
+    radius: 50.0,
+    origin_impulse: 3500.0,
+};
+
+#[derive(Component, Clone)]
 pub struct Explosion {
-    radius: f32,
-    origin_impulse: f32,
+    pub radius: f32,
+    pub origin_impulse: f32,
 }
 
 impl Explosion {
@@ -57,17 +65,23 @@ impl Explosion {
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Torpedo {
     explosion_timer: Timer,
     active_timer: Timer,
+    warhead: Explosion,
 }
 
+/// A torpedo fired from a ship's own stock, rather than by a base or the enemy.
+#[derive(Component, Clone)]
+pub struct ShipLaunched;
+
 impl Torpedo {
-    pub fn new() -> Torpedo {
+    pub fn new(fuse: Duration, warhead: Explosion) -> Torpedo {
         Torpedo {
-            explosion_timer: Timer::new(Duration::from_millis(2500), TimerMode::Once),
+            explosion_timer: Timer::new(fuse, TimerMode::Once),
             active_timer: Timer::new(Duration::from_millis(0), TimerMode::Once),
+            warhead,
         }
     }
 
@@ -81,47 +95,97 @@ impl Torpedo {
     }
 
     fn explode(&self) -> Explosion {
-        Explosion {
-            radius: 50.0,
-            origin_impulse: 3500.0,
-        }
+        self.warhead.clone()
+    }
+}
+
+/// Launches a torpedo from `trans`, drawn once the UI sees it. Torpedoes
+/// without a team score for nobody.
+pub fn spawn_torpedo(
+    commands: &mut Commands,
+    trans: Transform,
+    velocity: Velocity,
+    team: Option<Team>,
+    torpedo: Torpedo,
+) -> Entity {
+    let entity = commands
+        .spawn((
+            TransformBundle::from_transform(trans),
+            RigidBody::Dynamic,
+            Damping {
+                linear_damping: 0.2,
+                angular_damping: 1.5,
+            },
+            Collider::cuboid(5.0, 3.0),
+            CollisionGroups::new(
+                Group::from_bits(CollisionMemberships::KineticWeapon as u32).unwrap(),
+                Group::from_bits(CollisionFilters::KineticWeapon as u32).unwrap(),
+            ),
+            ColliderMassProperties::Mass(WEAPON_MASS),
+            ActiveEvents::COLLISION_EVENTS,
+            Weapon,
+            ExternalForce {
+                force: Vec2::ZERO,
+                torque: 0.0,
+            },
+            velocity,
+            torpedo,
+        ))
+        .id();
+    if let Some(team) = team {
+        commands.entity(entity).insert(team);
     }
+    entity
 }
 
 fn torpedo_impact(
     mut commands: Commands,
-    weapon: Query<(Entity, &Torpedo, &Transform), With<Torpedo>>,
+    weapon: Query<(Entity, &Torpedo, &Transform, Option<&Team>), With<Torpedo>>,
     mut collision_events: EventReader<CollisionEvent>,
 ) {
     for collision_event in collision_events.iter() {
-        for (entity, torp, trans) in weapon.iter() {
+        for (entity, torp, trans, team) in weapon.iter() {
             if let CollisionEvent::Started(h1, h2, _event_flag) = collision_event {
                 if torp.can_explode() && ((*h1 == entity) || (*h2 == entity)) {
-                    spawn_explosion(&mut commands, entity, torp, trans);
+                    spawn_explosion(&mut commands, entity, torp, trans, team);
                 };
             };
         }
     }
 }
 
-pub fn spawn_explosion(commands: &mut Commands, entity: Entity, source: &Torpedo, loc: &Transform) {
+/// Replaces a torpedo with its explosion, which keeps the team that fired it.
+pub fn spawn_explosion(
+    commands: &mut Commands,
+    entity: Entity,
+    source: &Torpedo,
+    loc: &Transform,
+    team: Option<&Team>,
+) {
     let trans = TransformBundle::from_transform(*loc);
-    commands
+    let explosion = commands
         .spawn((
         trans,
-        source.explode()));
+        source.explode()))
+        .id();
+    if let Some(team) = team {
+        commands.entity(explosion).insert(*team);
+    }
     commands.entity(entity).despawn();
 }
 
 fn weapon_movement(
     mut commands: Commands,
-    mut weapons: Query<(Entity, &mut Torpedo, &Transform, &mut ExternalForce), With<Torpedo>>,
-    time: Res<Time>,
+    mut weapons: Query<
+        (Entity, &mut Torpedo, &Transform, &mut ExternalForce, Option<&Team>),
+        With<Torpedo>,
+    >,
+    time: GameTime,
 ) {
-    for (entity, mut torp, trans, mut force) in weapons.iter_mut() {
+    for (entity, mut torp, trans, mut force, team) in weapons.iter_mut() {
         torp.tick(time.delta());
         if torp.explosion_timer.finished() {
-            spawn_explosion(&mut commands, entity, &*torp, trans);
+            spawn_explosion(&mut commands, entity, &*torp, trans, team);
         } else {
             let bf = BodyForce::new(0.0, 1.0, WEAPON_FORCE_PROFILE);
             force.force = bf.force_from_transform(trans);